@@ -1,19 +1,37 @@
 """Python bindings for the mesh-thumbnail renderer."""
 
 from ._core import (  # type: ignore[attr-defined]
+    FORMAT_AVIF,
+    FORMAT_BMP,
     FORMAT_JPG,
     FORMAT_PNG,
+    FORMAT_QOI,
+    FORMAT_TIFF,
+    FORMAT_WEBP,
     MeshThumbnailError,
+    ThumbnailImage,
     ThumbnailOptions,
+    generate_overview_bytes,
+    generate_overview_for_paths,
     generate_thumbnail_bytes,
     generate_thumbnail_for_file,
+    generate_thumbnail_images,
 )
 
 __all__ = [
     "ThumbnailOptions",
+    "ThumbnailImage",
     "generate_thumbnail_for_file",
     "generate_thumbnail_bytes",
+    "generate_thumbnail_images",
+    "generate_overview_for_paths",
+    "generate_overview_bytes",
     "MeshThumbnailError",
     "FORMAT_PNG",
     "FORMAT_JPG",
+    "FORMAT_WEBP",
+    "FORMAT_AVIF",
+    "FORMAT_BMP",
+    "FORMAT_TIFF",
+    "FORMAT_QOI",
 ]
@@ -0,0 +1,447 @@
+use std::borrow::Cow;
+
+use clap::ValueEnum;
+use color_quant::NeuQuant;
+use image::{ExtendedColorType, ImageEncoder, RgbaImage, codecs::webp::WebPEncoder};
+
+use crate::ThumbnailError;
+
+/// Container for turntable animations.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+    Webp,
+}
+
+impl ToString for AnimationFormat {
+    fn to_string(&self) -> String {
+        match self {
+            AnimationFormat::Gif => String::from("gif"),
+            AnimationFormat::Apng => String::from("apng"),
+            AnimationFormat::Webp => String::from("webp"),
+        }
+    }
+}
+
+impl AnimationFormat {
+    /// File extension of the container, APNG files use the regular png extension.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+            AnimationFormat::Webp => "webp",
+        }
+    }
+}
+
+/// Palette handling for GIF animations.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum GifPalette {
+    /// One palette shared by all frames, avoids colors flickering between frames.
+    #[default]
+    Global,
+    /// A palette per frame, more accurate colors but larger files.
+    PerFrame,
+}
+
+impl ToString for GifPalette {
+    fn to_string(&self) -> String {
+        match self {
+            GifPalette::Global => String::from("global"),
+            GifPalette::PerFrame => String::from("per-frame"),
+        }
+    }
+}
+
+///
+/// Encodes equally sized frames into a single animation.
+/// `loop_count` is the number of times the animation plays, 0 plays it forever.
+///
+pub fn encode_animation(
+    frames: &[RgbaImage],
+    format: AnimationFormat,
+    frame_rate: f32,
+    loop_count: u32,
+    palette: GifPalette,
+) -> Result<Vec<u8>, ThumbnailError> {
+    if frames.is_empty() {
+        return Err(ThumbnailError::Other(String::from(
+            "Animation needs at least one frame",
+        )));
+    }
+
+    let frame_duration = 1000.0 / frame_rate.max(0.1);
+
+    match format {
+        AnimationFormat::Gif => encode_gif(frames, frame_duration, loop_count, palette),
+        AnimationFormat::Apng => encode_apng(frames, frame_duration, loop_count),
+        AnimationFormat::Webp => encode_webp(frames, frame_duration, loop_count),
+    }
+}
+
+// Pixels at or above this alpha are opaque in formats with a single transparent index
+const GIF_ALPHA_THRESHOLD: u8 = 128;
+
+// Upper bound of pixels fed to the quantizer for the global palette, sampled evenly over all frames
+const GIF_PALETTE_SAMPLES: usize = 1 << 20;
+
+fn encode_gif(
+    frames: &[RgbaImage],
+    frame_duration: f32,
+    loop_count: u32,
+    palette: GifPalette,
+) -> Result<Vec<u8>, ThumbnailError> {
+    let width = frames[0].width() as u16;
+    let height = frames[0].height() as u16;
+    // GIF delays are in hundredths of a second, browsers slow down anything below two
+    let delay = ((frame_duration / 10.0).round() as u16).max(2);
+    let mut buffer = Vec::new();
+
+    let quantizer = match palette {
+        GifPalette::Global => Some(global_quantizer(frames)),
+        GifPalette::PerFrame => None,
+    };
+    let global_palette = match &quantizer {
+        Some(quantizer) => {
+            // The last entry is reserved for transparent pixels
+            let mut colors = quantizer.color_map_rgb();
+            colors.resize(256 * 3, 0);
+            colors
+        }
+        None => Vec::new(),
+    };
+
+    {
+        let mut encoder = gif::Encoder::new(&mut buffer, width, height, &global_palette)
+            .map_err(|e| ThumbnailError::Other(e.to_string()))?;
+
+        // One play means no repetition at all, which is a GIF without the loop extension
+        if loop_count != 1 {
+            let repeat = match loop_count {
+                0 => gif::Repeat::Infinite,
+                plays => gif::Repeat::Finite((plays - 1).min(u16::MAX as u32) as u16),
+            };
+            encoder
+                .set_repeat(repeat)
+                .map_err(|e| ThumbnailError::Other(e.to_string()))?;
+        }
+
+        for image in frames {
+            let mut frame = match &quantizer {
+                Some(quantizer) => gif::Frame {
+                    width,
+                    height,
+                    buffer: Cow::Owned(
+                        image
+                            .pixels()
+                            .map(|pixel| {
+                                if pixel[3] < GIF_ALPHA_THRESHOLD {
+                                    255
+                                } else {
+                                    quantizer.index_of(&[pixel[0], pixel[1], pixel[2], 255]) as u8
+                                }
+                            })
+                            .collect(),
+                    ),
+                    transparent: Some(255),
+                    ..Default::default()
+                },
+                None => {
+                    let mut rgba = image.as_raw().clone();
+                    gif::Frame::from_rgba_speed(width, height, &mut rgba, 10)
+                }
+            };
+
+            frame.delay = delay;
+            // Every frame covers the whole canvas, clear it so transparent areas do not keep the previous frame
+            frame.dispose = gif::DisposalMethod::Background;
+            encoder
+                .write_frame(&frame)
+                .map_err(|e| ThumbnailError::Other(e.to_string()))?;
+        }
+    }
+
+    Ok(buffer)
+}
+
+/// Builds a 255 color palette from the opaque pixels of all frames.
+fn global_quantizer(frames: &[RgbaImage]) -> NeuQuant {
+    let total: usize = frames
+        .iter()
+        .map(|frame| (frame.width() * frame.height()) as usize)
+        .sum();
+    let step = total / GIF_PALETTE_SAMPLES + 1;
+
+    let mut samples: Vec<u8> = frames
+        .iter()
+        .flat_map(|frame| frame.pixels())
+        .step_by(step)
+        .filter(|pixel| pixel[3] >= GIF_ALPHA_THRESHOLD)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+        .collect();
+
+    if samples.is_empty() {
+        samples.extend_from_slice(&[0, 0, 0, 255]);
+    }
+
+    NeuQuant::new(10, 255, &samples)
+}
+
+fn encode_apng(
+    frames: &[RgbaImage],
+    frame_duration: f32,
+    loop_count: u32,
+) -> Result<Vec<u8>, ThumbnailError> {
+    let mut buffer = Vec::new();
+    let error = |e: png::EncodingError| ThumbnailError::Other(e.to_string());
+
+    {
+        let mut encoder = png::Encoder::new(&mut buffer, frames[0].width(), frames[0].height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(frames.len() as u32, loop_count)
+            .map_err(error)?;
+        encoder
+            .set_frame_delay(frame_duration.round().min(u16::MAX as f32) as u16, 1000)
+            .map_err(error)?;
+        encoder
+            .set_dispose_op(png::DisposeOp::Background)
+            .map_err(error)?;
+        encoder.set_blend_op(png::BlendOp::Source).map_err(error)?;
+
+        let mut writer = encoder.write_header().map_err(error)?;
+
+        for frame in frames {
+            writer.write_image_data(frame.as_raw()).map_err(error)?;
+        }
+
+        writer.finish().map_err(error)?;
+    }
+
+    Ok(buffer)
+}
+
+///
+/// Writes an animated WebP container around lossless frames.
+/// The image crate only encodes still WebP images, so every frame is encoded on its own and its VP8L chunk is moved into an ANMF chunk.
+///
+fn encode_webp(
+    frames: &[RgbaImage],
+    frame_duration: f32,
+    loop_count: u32,
+) -> Result<Vec<u8>, ThumbnailError> {
+    let width = frames[0].width();
+    let height = frames[0].height();
+    let duration = (frame_duration.round() as u32).min(0xFF_FFFF);
+
+    let mut body = Vec::new();
+    body.extend_from_slice(b"WEBP");
+
+    // VP8X header with the animation and alpha flags
+    let mut vp8x = vec![0x02 | 0x10, 0, 0, 0];
+    vp8x.extend_from_slice(&u24(width - 1));
+    vp8x.extend_from_slice(&u24(height - 1));
+    write_chunk(&mut body, b"VP8X", &vp8x);
+
+    // Transparent background, followed by the loop count
+    let mut anim = vec![0, 0, 0, 0];
+    anim.extend_from_slice(&(loop_count.min(u16::MAX as u32) as u16).to_le_bytes());
+    write_chunk(&mut body, b"ANIM", &anim);
+
+    for frame in frames {
+        let mut still = Vec::new();
+        WebPEncoder::new_lossless(&mut still)
+            .write_image(frame.as_raw(), width, height, ExtendedColorType::Rgba8)
+            .map_err(|e| ThumbnailError::Other(e.to_string()))?;
+
+        let bitstream = find_chunk(&still, b"VP8L").ok_or_else(|| {
+            ThumbnailError::Other(String::from("WebP encoder did not produce a VP8L chunk"))
+        })?;
+
+        let mut anmf = Vec::with_capacity(16 + bitstream.len() + 8);
+        anmf.extend_from_slice(&u24(0));
+        anmf.extend_from_slice(&u24(0));
+        anmf.extend_from_slice(&u24(width - 1));
+        anmf.extend_from_slice(&u24(height - 1));
+        anmf.extend_from_slice(&u24(duration));
+        // Do not blend with the previous frame, every frame is complete
+        anmf.push(0x02);
+        write_chunk(&mut anmf, b"VP8L", bitstream);
+        write_chunk(&mut body, b"ANMF", &anmf);
+    }
+
+    let mut buffer = Vec::with_capacity(body.len() + 8);
+    buffer.extend_from_slice(b"RIFF");
+    buffer.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(&body);
+
+    Ok(buffer)
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let bytes = value.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+// RIFF chunks are padded to an even size
+fn write_chunk(buffer: &mut Vec<u8>, fourcc: &[u8; 4], data: &[u8]) {
+    buffer.extend_from_slice(fourcc);
+    buffer.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        buffer.push(0);
+    }
+}
+
+fn find_chunk<'a>(riff: &'a [u8], fourcc: &[u8; 4]) -> Option<&'a [u8]> {
+    // Skip the RIFF header and the WEBP form type
+    let mut offset = 12;
+
+    while offset + 8 <= riff.len() {
+        let size = u32::from_le_bytes(riff[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let data = riff.get(offset + 8..offset + 8 + size)?;
+
+        if &riff[offset..offset + 4] == fourcc {
+            return Some(data);
+        }
+
+        offset += 8 + size + size % 2;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{
+        AnimationDecoder, Frame, Rgba,
+        codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    };
+
+    use super::*;
+
+    const FRAME_RATE: f32 = 20.0;
+    const LOOP_COUNT: u32 = 3;
+
+    // A square moving over a transparent background, in another color every frame.
+    // Large enough to give the GIF quantizer a realistic number of samples.
+    fn frames() -> Vec<RgbaImage> {
+        let colors = [[220, 40, 40, 255], [40, 180, 60, 255], [40, 80, 220, 255]];
+
+        colors
+            .iter()
+            .enumerate()
+            .map(|(i, color)| {
+                RgbaImage::from_fn(64, 48, |x, y| {
+                    let left = i as u32 * 16;
+                    if (left..left + 24).contains(&x) && (12..36).contains(&y) {
+                        Rgba(*color)
+                    } else {
+                        Rgba([0, 0, 0, 0])
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn encode(format: AnimationFormat, palette: GifPalette) -> Vec<u8> {
+        encode_animation(&frames(), format, FRAME_RATE, LOOP_COUNT, palette).unwrap()
+    }
+
+    fn assert_frames_match(decoded: Vec<Frame>, tolerance: u8) {
+        let expected = frames();
+        assert_eq!(decoded.len(), expected.len());
+
+        for (frame, expected) in decoded.iter().zip(&expected) {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            assert!((numer as f32 / denom as f32 - 1000.0 / FRAME_RATE).abs() < 0.01);
+            assert_eq!(frame.buffer().dimensions(), expected.dimensions());
+
+            for (pixel, expected) in frame.buffer().pixels().zip(expected.pixels()) {
+                if expected[3] == 0 {
+                    assert_eq!(pixel[3], 0);
+                } else {
+                    let close = pixel
+                        .0
+                        .iter()
+                        .zip(expected.0.iter())
+                        .all(|(a, b)| a.abs_diff(*b) <= tolerance);
+                    assert!(close, "{pixel:?} != {expected:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gif_round_trip() {
+        for palette in [GifPalette::Global, GifPalette::PerFrame] {
+            let encoded = encode(AnimationFormat::Gif, palette);
+
+            let decoder = GifDecoder::new(Cursor::new(&encoded)).unwrap();
+            // Quantization may shift colors slightly
+            assert_frames_match(decoder.into_frames().collect_frames().unwrap(), 8);
+
+            // Playing three times repeats twice
+            let decoder = gif::DecodeOptions::new()
+                .read_info(Cursor::new(&encoded))
+                .unwrap();
+            assert_eq!(decoder.repeat(), gif::Repeat::Finite(LOOP_COUNT as u16 - 1));
+        }
+    }
+
+    #[test]
+    fn gif_playing_once_has_no_loop_extension() {
+        let encoded = encode_animation(
+            &frames(),
+            AnimationFormat::Gif,
+            FRAME_RATE,
+            1,
+            GifPalette::Global,
+        )
+        .unwrap();
+        let decoder = gif::DecodeOptions::new()
+            .read_info(Cursor::new(&encoded))
+            .unwrap();
+
+        assert_eq!(decoder.repeat(), gif::Repeat::Finite(0));
+    }
+
+    #[test]
+    fn apng_round_trip() {
+        let encoded = encode(AnimationFormat::Apng, GifPalette::Global);
+
+        let decoder = PngDecoder::new(Cursor::new(&encoded)).unwrap();
+        assert_frames_match(
+            decoder
+                .apng()
+                .unwrap()
+                .into_frames()
+                .collect_frames()
+                .unwrap(),
+            0,
+        );
+
+        let reader = png::Decoder::new(Cursor::new(&encoded))
+            .read_info()
+            .unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!(control.num_frames, 3);
+        assert_eq!(control.num_plays, LOOP_COUNT);
+    }
+
+    #[test]
+    fn webp_round_trip() {
+        let encoded = encode(AnimationFormat::Webp, GifPalette::Global);
+
+        let decoder = WebPDecoder::new(Cursor::new(&encoded)).unwrap();
+        assert!(decoder.has_animation());
+        assert_frames_match(decoder.into_frames().collect_frames().unwrap(), 0);
+
+        let anim = find_chunk(&encoded, b"ANIM").unwrap();
+        assert_eq!(u16::from_le_bytes([anim[4], anim[5]]), LOOP_COUNT as u16);
+    }
+}
@@ -0,0 +1,238 @@
+use clap::ValueEnum;
+use three_d::*;
+
+use crate::text::{blend_pixel, draw_text, text_width};
+
+/// Units used to label model dimensions, the model itself is assumed to be in millimeters.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum DimensionUnits {
+    #[default]
+    Mm,
+    Cm,
+    Inch,
+}
+
+impl ToString for DimensionUnits {
+    fn to_string(&self) -> String {
+        match self {
+            DimensionUnits::Mm => String::from("mm"),
+            DimensionUnits::Cm => String::from("cm"),
+            DimensionUnits::Inch => String::from("inch"),
+        }
+    }
+}
+
+impl DimensionUnits {
+    /// Formats a length in millimeters in these units, including the unit suffix when `suffix` is set.
+    pub fn format(&self, millimeters: f32, suffix: bool) -> String {
+        let (value, decimals, unit) = match self {
+            DimensionUnits::Mm => (millimeters, 1, "mm"),
+            DimensionUnits::Cm => (millimeters / 10.0, 2, "cm"),
+            DimensionUnits::Inch => (millimeters / 25.4, 2, "in"),
+        };
+
+        if suffix {
+            format!("{:.*} {}", decimals, value, unit)
+        } else {
+            format!("{:.*}", decimals, value)
+        }
+    }
+}
+
+const TEXT_COLOR: [u8; 4] = [255, 255, 255, 255];
+const HALO_COLOR: [u8; 4] = [0, 0, 0, 200];
+const LINE_COLOR: [u8; 4] = [255, 160, 0, 255];
+
+/// Text size for an image, readable at thumbnail sizes without covering the model.
+fn text_size(width: usize, height: usize) -> f32 {
+    (width.min(height) as f32 * 0.045).max(10.0)
+}
+
+///
+/// Writes the X×Y×Z extents of the model into the bottom left corner of the image.
+/// `size` holds the extents along the printer axes in millimeters.
+///
+pub fn draw_dimension_caption(
+    pixels: &mut [[u8; 4]],
+    width: usize,
+    height: usize,
+    size: Vec3,
+    units: DimensionUnits,
+) {
+    let caption = format!(
+        "{} × {} × {}",
+        units.format(size.x, false),
+        units.format(size.y, false),
+        units.format(size.z, true)
+    );
+    let text_size = text_size(width, height);
+    let margin = text_size * 0.5;
+
+    draw_text(
+        pixels,
+        width,
+        &caption,
+        (margin, height as f32 - margin - text_size * 1.2),
+        text_size,
+        TEXT_COLOR,
+        Some(HALO_COLOR),
+    );
+}
+
+///
+/// Draws a dimension line with its length along one visible bounding box edge per axis.
+/// The bounding box is given in scene space, where the printer X, Y and Z axes are the scene X, -Z and Y axes.
+///
+pub fn draw_dimension_lines(
+    pixels: &mut [[u8; 4]],
+    width: usize,
+    height: usize,
+    camera: &Camera,
+    (min, max): (Vec3, Vec3),
+    units: DimensionUnits,
+) {
+    let view_projection = camera.projection() * camera.view();
+    let to_screen = |point: Vec3| {
+        let clip = view_projection * point.extend(1.0);
+        vec2(
+            (clip.x / clip.w + 1.0) / 2.0 * width as f32,
+            (1.0 - clip.y / clip.w) / 2.0 * height as f32,
+        )
+    };
+
+    let eye = camera.position();
+    let center = (min + max) / 2.0;
+    let screen_center = to_screen(center);
+    // Pick the bottom edges closest to the camera for the footprint
+    let near_x = if (eye.x - min.x).abs() < (eye.x - max.x).abs() {
+        min.x
+    } else {
+        max.x
+    };
+    let near_z = if (eye.z - min.z).abs() < (eye.z - max.z).abs() {
+        min.z
+    } else {
+        max.z
+    };
+
+    let mut edges = vec![
+        (
+            vec3(min.x, min.y, near_z),
+            vec3(max.x, min.y, near_z),
+            max.x - min.x,
+        ),
+        (
+            vec3(near_x, min.y, min.z),
+            vec3(near_x, min.y, max.z),
+            max.z - min.z,
+        ),
+    ];
+
+    // The height goes along the vertical edge furthest to the left on screen
+    let vertical = [
+        (min.x, min.z),
+        (min.x, max.z),
+        (max.x, min.z),
+        (max.x, max.z),
+    ]
+    .into_iter()
+    .map(|(x, z)| (vec3(x, min.y, z), vec3(x, max.y, z)))
+    .min_by(|a, b| to_screen(a.0).x.total_cmp(&to_screen(b.0).x));
+
+    if let Some((start, end)) = vertical {
+        edges.push((start, end, max.y - min.y));
+    }
+
+    let text_size = text_size(width, height);
+    let line_width = (text_size * 0.08).max(1.0);
+
+    for (start, end, length) in edges {
+        let a = to_screen(start);
+        let b = to_screen(end);
+        let direction = b - a;
+
+        // Edges pointing at the camera collapse to a point and have nothing to show
+        if direction.magnitude() < text_size {
+            continue;
+        }
+
+        let direction = direction.normalize();
+        let mut normal = vec2(-direction.y, direction.x);
+        let midpoint = (a + b) / 2.0;
+        if normal.dot(midpoint - screen_center) < 0.0 {
+            normal = -normal;
+        }
+
+        // Offset the line away from the model so it does not cover the edge itself
+        let offset = normal * text_size * 0.6;
+        let tick = normal * text_size * 0.3;
+        draw_line(pixels, width, height, a + offset, b + offset, line_width);
+        draw_line(
+            pixels,
+            width,
+            height,
+            a + offset - tick,
+            a + offset + tick,
+            line_width,
+        );
+        draw_line(
+            pixels,
+            width,
+            height,
+            b + offset - tick,
+            b + offset + tick,
+            line_width,
+        );
+
+        let label = units.format(length, true);
+        let label_width = text_width(&label, text_size);
+        // Half the extent of the label box along the normal, so the label clears the line
+        let extent = normal.x.abs() * label_width / 2.0 + normal.y.abs() * text_size * 0.6;
+        let anchor = midpoint + offset + normal * (text_size * 0.3 + extent);
+
+        draw_text(
+            pixels,
+            width,
+            &label,
+            (anchor.x - label_width / 2.0, anchor.y - text_size * 0.6),
+            text_size,
+            TEXT_COLOR,
+            Some(HALO_COLOR),
+        );
+    }
+}
+
+// Line with a soft edge of one pixel, drawn by testing the distance of every pixel in its bounding box
+fn draw_line(
+    pixels: &mut [[u8; 4]],
+    width: usize,
+    height: usize,
+    a: Vec2,
+    b: Vec2,
+    line_width: f32,
+) {
+    let radius = line_width / 2.0;
+    let min_x = (a.x.min(b.x) - radius - 1.0).floor().max(0.0) as usize;
+    let min_y = (a.y.min(b.y) - radius - 1.0).floor().max(0.0) as usize;
+    let max_x = ((a.x.max(b.x) + radius + 1.0).ceil().max(0.0) as usize).min(width);
+    let max_y = ((a.y.max(b.y) + radius + 1.0).ceil().max(0.0) as usize).min(height);
+    let segment = b - a;
+    let length2 = segment.magnitude2();
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let point = vec2(x as f32 + 0.5, y as f32 + 0.5);
+            let t = if length2 > 0.0 {
+                ((point - a).dot(segment) / length2).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let distance = (point - (a + segment * t)).magnitude();
+            let coverage = (radius + 0.5 - distance).clamp(0.0, 1.0);
+
+            if coverage > 0.0 {
+                blend_pixel(&mut pixels[y * width + x], LINE_COLOR, coverage);
+            }
+        }
+    }
+}
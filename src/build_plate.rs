@@ -0,0 +1,158 @@
+use clap::ValueEnum;
+use three_d::*;
+
+use crate::geometry::outline_bounds;
+use crate::wireframe::edge_transform;
+
+/// Outline of the build plate drawn under the model.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum BedShape {
+    #[default]
+    Rectangle,
+    /// A round bed as used by delta printers, the bed width is the diameter.
+    Circle,
+}
+
+impl ToString for BedShape {
+    fn to_string(&self) -> String {
+        match self {
+            BedShape::Rectangle => String::from("rectangle"),
+            BedShape::Circle => String::from("circle"),
+        }
+    }
+}
+
+const CIRCLE_SEGMENTS: usize = 64;
+
+// Upper bound for the grid lines per axis, a too fine spacing would otherwise create millions of instances
+const MAX_GRID_LINES: f32 = 500.0;
+
+/// Outline of a bed with the given shape and size in millimeters, centered on `center`.
+pub fn bed_outline(shape: BedShape, center: Vec2, width: f32, depth: f32) -> Vec<Vec2> {
+    match shape {
+        BedShape::Rectangle => vec![
+            center + vec2(-width, -depth) / 2.0,
+            center + vec2(width, -depth) / 2.0,
+            center + vec2(width, depth) / 2.0,
+            center + vec2(-width, depth) / 2.0,
+        ],
+        BedShape::Circle => (0..CIRCLE_SEGMENTS)
+            .map(|i| {
+                let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                center + vec2(angle.cos() * width, angle.sin() * depth) / 2.0
+            })
+            .collect(),
+    }
+}
+
+/// Flat plate filling the convex `outline` at `height`, triangulated as a fan around its centroid.
+pub fn plate_mesh(outline: &[Vec2], height: f32) -> CpuMesh {
+    let centroid = outline
+        .iter()
+        .fold(vec2(0.0, 0.0), |sum, point| sum + *point)
+        / outline.len() as f32;
+
+    let mut positions = vec![vec3(centroid.x, centroid.y, height)];
+    positions.extend(outline.iter().map(|point| vec3(point.x, point.y, height)));
+
+    let count = outline.len() as u32;
+    let indices = (0..count)
+        .flat_map(|i| [0, i + 1, (i + 1) % count + 1])
+        .collect();
+
+    CpuMesh {
+        positions: Positions::F32(positions),
+        indices: Indices::U32(indices),
+        ..Default::default()
+    }
+}
+
+///
+/// Builds one cylinder instance per grid line inside the convex `outline` and per outline edge.
+/// Grid lines lie on multiples of `spacing`, so they line up with the bed origin of the printer.
+///
+pub fn grid_instances(outline: &[Vec2], spacing: f32, height: f32, radius: f32) -> Instances {
+    let mut lines: Vec<(Vec2, Vec2)> = outline
+        .iter()
+        .zip(outline.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b))
+        .collect();
+
+    let (min, max) = outline_bounds(outline);
+
+    let spacing = spacing.max((max.x - min.x).max(max.y - min.y) / MAX_GRID_LINES);
+
+    if spacing > 0.0 {
+        for axis in 0..2 {
+            let other = 1 - axis;
+            let mut value = (min[axis] / spacing).ceil() * spacing;
+
+            while value <= max[axis] {
+                if let Some((start, end)) = clip_line(outline, axis, value) {
+                    let mut p1 = vec2(0.0, 0.0);
+                    let mut p2 = vec2(0.0, 0.0);
+                    p1[axis] = value;
+                    p2[axis] = value;
+                    p1[other] = start;
+                    p2[other] = end;
+                    lines.push((p1, p2));
+                }
+                value += spacing;
+            }
+        }
+    }
+
+    Instances {
+        transformations: lines
+            .into_iter()
+            .filter(|(p1, p2)| p1 != p2)
+            .map(|(p1, p2)| {
+                edge_transform(vec3(p1.x, p1.y, height), vec3(p2.x, p2.y, height), radius)
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+// Range covered by the convex `outline` along the line where the `axis` component equals `value`
+fn clip_line(outline: &[Vec2], axis: usize, value: f32) -> Option<(f32, f32)> {
+    let other = 1 - axis;
+    let mut range: Option<(f32, f32)> = None;
+
+    for (a, b) in outline.iter().zip(outline.iter().cycle().skip(1)) {
+        if (a[axis] - value) * (b[axis] - value) > 0.0 || a[axis] == b[axis] {
+            continue;
+        }
+
+        let t = (value - a[axis]) / (b[axis] - a[axis]);
+        let position = a[other] + t * (b[other] - a[other]);
+
+        range = Some(match range {
+            Some((start, end)) => (start.min(position), end.max(position)),
+            None => (position, position),
+        });
+    }
+
+    range.filter(|(start, end)| end > start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(points: &[(f32, f32)]) -> Vec<Vec2> {
+        points.iter().map(|(x, y)| vec2(*x, *y)).collect()
+    }
+
+    #[test]
+    fn clip_line_spans_the_outline() {
+        let square = outline(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)]);
+        let diamond = outline(&[(0.0, -10.0), (10.0, 0.0), (0.0, 10.0), (-10.0, 0.0)]);
+
+        assert_eq!(clip_line(&square, 0, 30.0), Some((0.0, 100.0)));
+        assert_eq!(clip_line(&square, 1, 100.0), Some((0.0, 100.0)));
+        assert_eq!(clip_line(&square, 0, 120.0), None);
+        assert_eq!(clip_line(&diamond, 0, 5.0), Some((-5.0, 5.0)));
+        assert_eq!(clip_line(&diamond, 1, 10.0), None);
+    }
+}
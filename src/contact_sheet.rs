@@ -0,0 +1,73 @@
+use crate::text::{draw_text, text_width};
+
+const LABEL_COLOR: [u8; 4] = [255, 255, 255, 255];
+const LABEL_HALO_COLOR: [u8; 4] = [0, 0, 0, 200];
+
+/// Number of columns used for `count` cells, `columns` of 0 picks a roughly square grid.
+pub fn grid_columns(count: usize, columns: u32) -> usize {
+    let columns = if columns == 0 {
+        (count as f32).sqrt().ceil() as usize
+    } else {
+        columns as usize
+    };
+
+    columns.clamp(1, count.max(1))
+}
+
+/// Size in pixels of a sheet holding `count` cells of `cell_width` x `cell_height`.
+pub fn sheet_size(count: usize, columns: u32, cell_width: u32, cell_height: u32) -> (u32, u32) {
+    let columns = grid_columns(count, columns);
+    let rows = count.max(1).div_ceil(columns);
+
+    (cell_width * columns as u32, cell_height * rows as u32)
+}
+
+///
+/// Arranges equally sized cells row by row into one image, filling unused cells with `background`.
+/// When `labels` are given, each label is written centered at the top of its cell.
+///
+pub fn compose_sheet(
+    cells: Vec<Vec<[u8; 4]>>,
+    labels: Option<&[String]>,
+    (cell_width, cell_height): (u32, u32),
+    columns: u32,
+    background: [u8; 4],
+) -> Vec<[u8; 4]> {
+    let columns_used = grid_columns(cells.len(), columns);
+    let (width, height) = sheet_size(cells.len(), columns, cell_width, cell_height);
+    let (width, cell_width, cell_height) =
+        (width as usize, cell_width as usize, cell_height as usize);
+    let mut pixels = vec![background; width * height as usize];
+
+    for (index, cell) in cells.iter().enumerate() {
+        let left = (index % columns_used) * cell_width;
+        let top = (index / columns_used) * cell_height;
+
+        for (row, line) in cell.chunks_exact(cell_width).enumerate() {
+            let start = (top + row) * width + left;
+            pixels[start..start + cell_width].copy_from_slice(line);
+        }
+    }
+
+    if let Some(labels) = labels {
+        let size = (cell_width.min(cell_height) as f32 * 0.06).max(10.0);
+
+        for (index, label) in labels.iter().enumerate().take(cells.len()) {
+            let left = (index % columns_used * cell_width) as f32;
+            let top = (index / columns_used * cell_height) as f32;
+            let x = left + (cell_width as f32 - text_width(label, size)) / 2.0;
+
+            draw_text(
+                &mut pixels,
+                width,
+                label,
+                (x, top + size * 0.4),
+                size,
+                LABEL_COLOR,
+                Some(LABEL_HALO_COLOR),
+            );
+        }
+    }
+
+    pixels
+}
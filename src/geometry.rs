@@ -0,0 +1,112 @@
+use three_d::*;
+
+///
+/// `outline` itself when it is convex, otherwise its bounding rectangle.
+/// The plate and the grid are only drawn correctly for convex outlines, which covers the rectangular and round beds of common printers.
+///
+pub fn convex_outline(outline: Vec<Vec2>) -> Vec<Vec2> {
+    if is_convex(&outline) {
+        return outline;
+    }
+
+    let (min, max) = outline_bounds(&outline);
+    vec![min, vec2(max.x, min.y), max, vec2(min.x, max.y)]
+}
+
+/// Smallest and largest coordinates of `outline`.
+pub fn outline_bounds(outline: &[Vec2]) -> (Vec2, Vec2) {
+    outline.iter().fold(
+        (
+            vec2(f32::INFINITY, f32::INFINITY),
+            vec2(f32::NEG_INFINITY, f32::NEG_INFINITY),
+        ),
+        |(min, max), point| {
+            (
+                vec2(min.x.min(point.x), min.y.min(point.y)),
+                vec2(max.x.max(point.x), max.y.max(point.y)),
+            )
+        },
+    )
+}
+
+// Every corner turns the same way and the outline goes around only once, repeated and collinear points are allowed
+fn is_convex(outline: &[Vec2]) -> bool {
+    let edges: Vec<Vec2> = outline
+        .iter()
+        .zip(outline.iter().cycle().skip(1))
+        .map(|(a, b)| *b - *a)
+        .filter(|edge| edge.magnitude2() > 0.0)
+        .collect();
+
+    let mut turn = 0.0f32;
+    let mut total_angle = 0.0f32;
+
+    for (a, b) in edges.iter().zip(edges.iter().cycle().skip(1)) {
+        let cross = a.perp_dot(*b);
+        if cross * turn < 0.0 {
+            return false;
+        }
+        if cross != 0.0 {
+            turn = cross;
+        }
+        total_angle += cross.atan2(a.dot(*b));
+    }
+
+    turn != 0.0 && (total_angle.abs() - std::f32::consts::TAU).abs() < 1e-3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(points: &[(f32, f32)]) -> Vec<Vec2> {
+        points.iter().map(|(x, y)| vec2(*x, *y)).collect()
+    }
+
+    #[test]
+    fn convex_outline_keeps_convex_beds() {
+        let rectangle = outline(&[(0.0, 0.0), (250.0, 0.0), (250.0, 210.0), (0.0, 210.0)]);
+        let circle: Vec<Vec2> = (0..64)
+            .map(|i| {
+                let angle = i as f32 / 64.0 * std::f32::consts::TAU;
+                vec2(angle.cos(), angle.sin()) * 90.0
+            })
+            .collect();
+        let clockwise = outline(&[(0.0, 0.0), (0.0, 210.0), (250.0, 210.0), (250.0, 0.0)]);
+
+        for shape in [rectangle, circle, clockwise] {
+            assert_eq!(convex_outline(shape.clone()), shape);
+        }
+    }
+
+    #[test]
+    fn convex_outline_allows_repeated_and_collinear_points() {
+        let shape = outline(&[
+            (0.0, 0.0),
+            (125.0, 0.0),
+            (250.0, 0.0),
+            (250.0, 210.0),
+            (0.0, 210.0),
+            (0.0, 0.0),
+        ]);
+
+        assert_eq!(convex_outline(shape.clone()), shape);
+    }
+
+    #[test]
+    fn convex_outline_replaces_concave_and_crossing_shapes_by_their_bounds() {
+        let l_shape = outline(&[
+            (0.0, 0.0),
+            (200.0, 0.0),
+            (200.0, 100.0),
+            (100.0, 100.0),
+            (100.0, 200.0),
+            (0.0, 200.0),
+        ]);
+        let bow_tie = outline(&[(0.0, 0.0), (200.0, 200.0), (200.0, 0.0), (0.0, 200.0)]);
+        let bounds = outline(&[(0.0, 0.0), (200.0, 0.0), (200.0, 200.0), (0.0, 200.0)]);
+
+        assert_eq!(convex_outline(l_shape), bounds);
+        assert_eq!(convex_outline(bow_tie), bounds);
+    }
+}
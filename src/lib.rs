@@ -1,28 +1,59 @@
 use base64::{Engine, prelude::BASE64_STANDARD};
 use clap::ValueEnum;
 use image::{
-    DynamicImage, ImageFormat, ImageReader, RgbaImage, imageops::FilterType::Triangle,
+    DynamicImage, ExtendedColorType, ImageBuffer, ImageEncoder, ImageFormat, ImageReader, Pixel,
+    Rgba, RgbaImage,
+    codecs::{avif::AvifEncoder, jpeg::JpegEncoder},
+    imageops,
+    imageops::FilterType::{self, Lanczos3, Triangle},
 };
 use std::fs::File;
 use std::io::{BufRead, BufReader, Cursor, Read};
 use std::path::{self, Path, PathBuf};
+use surface_maps::SurfaceMaps;
+use three_d::context::HasContext;
 use three_d::*;
-use three_d_asset::io::Serialize;
 use zip::{ZipArchive, result::ZipError};
 
+mod animation;
+mod annotation;
+mod build_plate;
+mod contact_sheet;
+mod geometry;
+mod occlusion;
+mod orientation;
+mod outline;
+mod overview;
 pub mod parse_mesh;
+mod png_output;
+mod screen_space;
 pub mod solid_material;
+mod surface_maps;
+mod text;
+mod wireframe;
 
 #[cfg(feature = "python")]
 mod python;
 
+pub use animation::{AnimationFormat, GifPalette};
+pub use annotation::DimensionUnits;
+pub use build_plate::BedShape;
+pub use orientation::AutoOrient;
 pub use parse_mesh::{MeshWithTransform, ParseError, ParseResult};
-pub use solid_material::SolidMaterial;
+pub use png_output::PngCompression;
+pub use solid_material::{
+    HeightGradient, LightingRig, MaterialPreset, OverhangHighlight, SolidMaterial,
+};
 
 #[derive(Debug, Clone, ValueEnum, PartialEq, Eq)]
 pub enum Format {
     Jpg,
     Png,
+    Webp,
+    Avif,
+    Bmp,
+    Tiff,
+    Qoi,
 }
 
 impl ToString for Format {
@@ -30,6 +61,242 @@ impl ToString for Format {
         match self {
             Format::Jpg => String::from("jpg"),
             Format::Png => String::from("png"),
+            Format::Webp => String::from("webp"),
+            Format::Avif => String::from("avif"),
+            Format::Bmp => String::from("bmp"),
+            Format::Tiff => String::from("tiff"),
+            Format::Qoi => String::from("qoi"),
+        }
+    }
+}
+
+impl Format {
+    /// Whether the format keeps the alpha channel, formats without it get an opaque background.
+    pub fn has_alpha(&self) -> bool {
+        !matches!(self, Format::Jpg | Format::Bmp)
+    }
+}
+
+///
+/// Which thumbnail to use when a 3mf or gcode file embeds several.
+/// `Main` keeps the long standing pick of `thumbnail_middle.png` in 3mf files and the largest thumbnail in gcode files.
+///
+#[derive(Debug, Clone, ValueEnum, PartialEq, Eq)]
+pub enum ThumbnailSelection {
+    Main,
+    Largest,
+    SmallestFitting,
+    ClosestAspect,
+}
+
+impl ToString for ThumbnailSelection {
+    fn to_string(&self) -> String {
+        match self {
+            ThumbnailSelection::Main => String::from("main"),
+            ThumbnailSelection::Largest => String::from("largest"),
+            ThumbnailSelection::SmallestFitting => String::from("smallest-fitting"),
+            ThumbnailSelection::ClosestAspect => String::from("closest-aspect"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum, PartialEq, Eq)]
+pub enum Projection {
+    Perspective,
+    Orthographic,
+}
+
+impl ToString for Projection {
+    fn to_string(&self) -> String {
+        match self {
+            Projection::Perspective => String::from("perspective"),
+            Projection::Orthographic => String::from("orthographic"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum, PartialEq, Eq)]
+pub enum ViewPreset {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Iso,
+    Dimetric,
+}
+
+impl ViewPreset {
+    /// Returns the `(rotatex, rotatey)` camera angles in degrees for this view.
+    pub fn angles(&self) -> (f32, f32) {
+        match self {
+            ViewPreset::Front => (0.0, 0.0),
+            ViewPreset::Back => (180.0, 0.0),
+            ViewPreset::Left => (-90.0, 0.0),
+            ViewPreset::Right => (90.0, 0.0),
+            ViewPreset::Top => (0.0, 90.0),
+            ViewPreset::Bottom => (0.0, -90.0),
+            // atan(1 / sqrt(2)), all three axes equally foreshortened
+            ViewPreset::Iso => (45.0, 35.264),
+            // DIN ISO 5456-3 dimetric, depth axis foreshortened to half
+            ViewPreset::Dimetric => (20.705, 19.471),
+        }
+    }
+
+    /// Returns the capitalized name of this view, used to label contact sheet cells.
+    pub fn label(&self) -> String {
+        let name = self.to_string();
+        let mut characters = name.chars();
+        match characters.next() {
+            Some(first) => first.to_uppercase().chain(characters).collect(),
+            None => name,
+        }
+    }
+}
+
+impl ToString for ViewPreset {
+    fn to_string(&self) -> String {
+        match self {
+            ViewPreset::Front => String::from("front"),
+            ViewPreset::Back => String::from("back"),
+            ViewPreset::Left => String::from("left"),
+            ViewPreset::Right => String::from("right"),
+            ViewPreset::Top => String::from("top"),
+            ViewPreset::Bottom => String::from("bottom"),
+            ViewPreset::Iso => String::from("iso"),
+            ViewPreset::Dimetric => String::from("dimetric"),
+        }
+    }
+}
+
+/// How parts without colors from the file are colored.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Every part in the model color.
+    #[default]
+    Single,
+    /// A gradient along the print height, overrides the colors from the file.
+    Height,
+    /// A different color from a palette for each part, the volumes of a 3MF file or the objects of an OBJ file.
+    /// STL files hold a single part and get the first palette color.
+    Part,
+    /// A color derived from the file name, the same file always gets the same color.
+    FileName,
+}
+
+impl ToString for ColorMode {
+    fn to_string(&self) -> String {
+        match self {
+            ColorMode::Single => String::from("single"),
+            ColorMode::Height => String::from("height"),
+            ColorMode::Part => String::from("part"),
+            ColorMode::FileName => String::from("file-name"),
+        }
+    }
+}
+
+/// Axis pointing up in the coordinates of a mesh file, G-code always uses the printer axes.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum UpAxis {
+    /// Z up for STL and 3MF, Y up for OBJ as written by most modeling tools.
+    #[default]
+    Auto,
+    Y,
+    Z,
+}
+
+impl ToString for UpAxis {
+    fn to_string(&self) -> String {
+        match self {
+            UpAxis::Auto => String::from("auto"),
+            UpAxis::Y => String::from("y"),
+            UpAxis::Z => String::from("z"),
+        }
+    }
+}
+
+/// Unit of the mesh coordinates, everything is rendered and measured in millimeters.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum ModelUnits {
+    /// The unit declared by the file, which only 3MF does, otherwise millimeters.
+    #[default]
+    Auto,
+    Mm,
+    Cm,
+    M,
+    Inch,
+}
+
+impl ModelUnits {
+    /// Returns the millimeters per unit, `None` when the file decides.
+    pub fn millimeters(&self) -> Option<f32> {
+        match self {
+            ModelUnits::Auto => None,
+            ModelUnits::Mm => Some(1.0),
+            ModelUnits::Cm => Some(10.0),
+            ModelUnits::M => Some(1000.0),
+            ModelUnits::Inch => Some(25.4),
+        }
+    }
+}
+
+impl ToString for ModelUnits {
+    fn to_string(&self) -> String {
+        match self {
+            ModelUnits::Auto => String::from("auto"),
+            ModelUnits::Mm => String::from("mm"),
+            ModelUnits::Cm => String::from("cm"),
+            ModelUnits::M => String::from("m"),
+            ModelUnits::Inch => String::from("inch"),
+        }
+    }
+}
+
+/// Printer axis used as the normal of a section plane.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum SectionAxis {
+    X,
+    Y,
+    Z,
+}
+
+impl SectionAxis {
+    /// Returns the axis as a normal in print coordinates.
+    pub fn normal(&self) -> [f32; 3] {
+        match self {
+            SectionAxis::X => [1.0, 0.0, 0.0],
+            SectionAxis::Y => [0.0, 1.0, 0.0],
+            SectionAxis::Z => [0.0, 0.0, 1.0],
+        }
+    }
+}
+
+impl ToString for SectionAxis {
+    fn to_string(&self) -> String {
+        match self {
+            SectionAxis::X => String::from("x"),
+            SectionAxis::Y => String::from("y"),
+            SectionAxis::Z => String::from("z"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum, PartialEq, Eq)]
+pub enum RenderMode {
+    Shaded,
+    Wireframe,
+    ShadedWireframe,
+    Points,
+}
+
+impl ToString for RenderMode {
+    fn to_string(&self) -> String {
+        match self {
+            RenderMode::Shaded => String::from("shaded"),
+            RenderMode::Wireframe => String::from("wireframe"),
+            RenderMode::ShadedWireframe => String::from("shaded-wireframe"),
+            RenderMode::Points => String::from("points"),
         }
     }
 }
@@ -48,6 +315,67 @@ pub struct ThumbnailOptions {
     pub prefer_gcode_thumbnail: bool,
     pub images_per_file: u32,
     pub inverse_zoom: f32,
+    pub thumbnail_selection: ThumbnailSelection,
+    pub letterbox_thumbnail: bool,
+    pub projection: Projection,
+    pub view: Option<ViewPreset>,
+    pub auto_frame: bool,
+    pub frame_margin: f32,
+    pub auto_crop: bool,
+    pub crop_margin: f32,
+    pub antialias: u32,
+    pub outline: bool,
+    pub outline_color: String,
+    pub outline_width: f32,
+    pub crease_angle: f32,
+    pub render_mode: RenderMode,
+    pub lighting: LightingRig,
+    pub material: MaterialPreset,
+    pub ambient: Option<f32>,
+    pub specular: Option<f32>,
+    pub ambient_occlusion: bool,
+    pub ao_strength: f32,
+    pub ground_shadow: bool,
+    pub shadow_strength: f32,
+    pub smooth_normals: bool,
+    pub smooth_angle: f32,
+    pub build_plate: bool,
+    pub grid_spacing: f32,
+    pub bed_shape: BedShape,
+    pub bed_width: Option<f32>,
+    pub bed_depth: Option<f32>,
+    pub dimensions: bool,
+    pub dimension_lines: bool,
+    pub dimension_units: DimensionUnits,
+    pub animation: Option<AnimationFormat>,
+    pub frame_rate: f32,
+    pub loop_count: u32,
+    pub gif_palette: GifPalette,
+    pub contact_sheet: Vec<ViewPreset>,
+    pub sheet_columns: u32,
+    pub sheet_labels: bool,
+    pub tile_size: u32,
+    pub overview_columns: u32,
+    pub explode: f32,
+    pub section: Option<SectionAxis>,
+    pub section_normal: Option<[f32; 3]>,
+    pub section_offset: f32,
+    pub section_color: String,
+    pub overhangs: bool,
+    pub overhang_angle: f32,
+    pub overhang_color: String,
+    pub bed_contact_color: String,
+    pub color_mode: ColorMode,
+    pub auto_orient: Option<AutoOrient>,
+    pub up_axis: UpAxis,
+    pub units: ModelUnits,
+    pub quality: u8,
+    pub lossless: bool,
+    pub jpeg_quality: u8,
+    pub png_compression: PngCompression,
+    pub png_palette: Option<u16>,
+    pub depth_map: bool,
+    pub normal_map: bool,
 }
 
 impl Default for ThumbnailOptions {
@@ -65,6 +393,67 @@ impl Default for ThumbnailOptions {
             prefer_gcode_thumbnail: false,
             images_per_file: 1,
             inverse_zoom: 1.0,
+            thumbnail_selection: ThumbnailSelection::Main,
+            letterbox_thumbnail: false,
+            projection: Projection::Perspective,
+            view: None,
+            auto_frame: false,
+            frame_margin: 5.0,
+            auto_crop: false,
+            crop_margin: 5.0,
+            antialias: 1,
+            outline: false,
+            outline_color: String::from("000000"),
+            outline_width: 1.0,
+            crease_angle: 40.0,
+            render_mode: RenderMode::Shaded,
+            lighting: LightingRig::Headlight,
+            material: MaterialPreset::Matte,
+            ambient: None,
+            specular: None,
+            ambient_occlusion: false,
+            ao_strength: 1.0,
+            ground_shadow: false,
+            shadow_strength: 0.5,
+            smooth_normals: false,
+            smooth_angle: 30.0,
+            build_plate: false,
+            grid_spacing: 10.0,
+            bed_shape: BedShape::Rectangle,
+            bed_width: None,
+            bed_depth: None,
+            dimensions: false,
+            dimension_lines: false,
+            dimension_units: DimensionUnits::Mm,
+            animation: None,
+            frame_rate: 15.0,
+            loop_count: 0,
+            gif_palette: GifPalette::Global,
+            contact_sheet: Vec::new(),
+            sheet_columns: 0,
+            sheet_labels: false,
+            tile_size: 256,
+            overview_columns: 0,
+            explode: 0.0,
+            section: None,
+            section_normal: None,
+            section_offset: 0.0,
+            section_color: String::from("E0452C"),
+            overhangs: false,
+            overhang_angle: 45.0,
+            overhang_color: String::from("E02020"),
+            bed_contact_color: String::from("2E7DD7"),
+            color_mode: ColorMode::Single,
+            auto_orient: None,
+            up_axis: UpAxis::Auto,
+            units: ModelUnits::Auto,
+            quality: 80,
+            lossless: false,
+            jpeg_quality: 90,
+            png_compression: PngCompression::Default,
+            png_palette: None,
+            depth_map: false,
+            normal_map: false,
         }
     }
 }
@@ -106,213 +495,225 @@ impl From<parse_mesh::ParseError> for ThumbnailError {
     }
 }
 
-pub fn generate_thumbnail_for_file(
-    file: &Path,
-    outdir: &Path,
-    options: &ThumbnailOptions,
-) -> Result<(), ThumbnailError> {
-    let mut options = options.clone();
+/// Origin of an [EncodedImage].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageSource {
+    /// Rendered from the parsed model.
+    Rendered,
+    /// Thumbnail embedded in a 3mf file.
+    Embedded3mf,
+    /// Thumbnail embedded in a G-code file.
+    EmbeddedGcode,
+}
 
-    if options.images_per_file < 1 {
-        options.images_per_file = 1;
+impl ToString for ImageSource {
+    fn to_string(&self) -> String {
+        match self {
+            ImageSource::Rendered => String::from("rendered"),
+            ImageSource::Embedded3mf => String::from("3mf"),
+            ImageSource::EmbeddedGcode => String::from("gcode"),
+        }
     }
+}
 
-    if options.images_per_file > 1 && options.rotatex != 0.0 {
-        options.rotatex = 0.0;
+/// What the pixels of an [EncodedImage] hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageKind {
+    /// The thumbnail itself.
+    Color,
+    /// 16 bit grayscale PNG, brighter is closer to the camera and 0 is the background.
+    Depth,
+    /// PNG of the view space normals mapped to RGB, X right, Y up and Z towards the camera.
+    Normal,
+}
+
+impl ToString for ImageKind {
+    fn to_string(&self) -> String {
+        match self {
+            ImageKind::Color => String::from("color"),
+            ImageKind::Depth => String::from("depth"),
+            ImageKind::Normal => String::from("normal"),
+        }
     }
+}
 
-    let viewport = Viewport::new_at_origo(options.width, options.height);
-    let context = HeadlessContext::new().map_err(|e| ThumbnailError::Other(e.to_string()))?;
-    let alpha = if options.format == Format::Jpg {
-        0.8
-    } else {
-        0.0
-    };
+/// An image generated for a model file, encoded in the requested format.
+#[derive(Debug, Clone)]
+pub struct EncodedImage {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// File extension matching the encoding, without the leading dot.
+    pub extension: String,
+    /// Index of the view when several images are generated per file, 0 otherwise.
+    pub view: u32,
+    pub source: ImageSource,
+    pub kind: ImageKind,
+}
 
-    let mut texture = Texture2D::new_empty::<[u8; 4]>(
-        &context,
-        viewport.width,
-        viewport.height,
-        Interpolation::Nearest,
-        Interpolation::Nearest,
-        None,
-        Wrapping::ClampToEdge,
-        Wrapping::ClampToEdge,
-    );
+/// The GPU resources shared by every render, created once and reused for all files.
+struct Renderer {
+    context: HeadlessContext,
+    viewport: Viewport,
+    texture: Texture2D,
+    depth_texture: DepthTexture2D,
+    alpha: f32,
+}
 
-    let mut depth_texture = DepthTexture2D::new::<f32>(
-        &context,
-        viewport.width,
-        viewport.height,
-        Wrapping::ClampToEdge,
-        Wrapping::ClampToEdge,
-    );
+impl Renderer {
+    fn new(options: &ThumbnailOptions) -> Result<Self, ThumbnailError> {
+        let context = HeadlessContext::new().map_err(|e| ThumbnailError::Other(e.to_string()))?;
+        let viewport = supersampled_viewport(&context, options);
+        let alpha = if !options.format.has_alpha() {
+            0.8
+        } else {
+            0.0
+        };
+
+        let texture = Texture2D::new_empty::<[u8; 4]>(
+            &context,
+            viewport.width,
+            viewport.height,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
 
-    generate_thumbnail_for_file_with_context(
-        &context,
-        &viewport,
-        file,
-        outdir,
-        alpha,
-        &mut texture,
-        &mut depth_texture,
-        &options,
-    )
+        let depth_texture = DepthTexture2D::new::<f32>(
+            &context,
+            viewport.width,
+            viewport.height,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+
+        Ok(Self {
+            context,
+            viewport,
+            texture,
+            depth_texture,
+            alpha,
+        })
+    }
+
+    fn render_frames(
+        &mut self,
+        parse_result: &parse_mesh::ParseResult,
+        file: &str,
+        options: &ThumbnailOptions,
+    ) -> Result<Vec<RenderedFrame>, ThumbnailError> {
+        render_frames(
+            &self.context,
+            &self.viewport,
+            &mut self.texture,
+            &mut self.depth_texture,
+            parse_result,
+            self.alpha,
+            file,
+            options,
+        )
+    }
 }
 
-fn generate_thumbnail_bytes_for_file_with_context(
-    context: &HeadlessContext,
-    viewport: &Viewport,
+///
+/// Generates the thumbnails of `file` and writes them to `outdir`, named after the file with the extension of the output format.
+/// Several images per file get the index of their view appended to the name, depth and normal maps a `-depth` or `-normal` suffix.
+///
+pub fn generate_thumbnail_for_file(
     file: &Path,
-    alpha: f32,
-    texture: &mut Texture2D,
-    depth_texture: &mut DepthTexture2D,
+    outdir: &Path,
     options: &ThumbnailOptions,
-) -> Result<Vec<u8>, ThumbnailError> {
-    let absolute_path = path::absolute(file)?;
-    let filename = absolute_path
-        .file_name()
-        .and_then(|s| s.to_str())
-        .ok_or_else(|| ThumbnailError::Other(String::from("Invalid filename")))?;
+) -> Result<(), ThumbnailError> {
+    let image_path = outdir.join(image_file_name(file, options)?);
 
-    if options.prefer_3mf_thumbnail && filename.ends_with(".3mf") {
-        if let Ok(bytes) = extract_image_from_3mf_to_bytes(
-            &absolute_path,
-            options.width,
-            options.height,
-            &options.format,
-        ) {
-            return Ok(bytes);
-        }
+    if !options.overwrite && image_path.exists() {
+        return Ok(());
     }
 
-    if options.prefer_gcode_thumbnail {
-        if filename.ends_with(".gcode") {
-            if let Ok(bytes) = extract_image_from_gcode_file_to_bytes(
-                &absolute_path,
-                options.width,
-                options.height,
-                &options.format,
-            ) {
-                return Ok(bytes);
-            }
-        } else if filename.ends_with(".gcode.zip") {
-            if let Ok(bytes) = extract_image_from_gcode_zip_to_bytes(
-                &absolute_path,
-                options.width,
-                options.height,
-                &options.format,
-            ) {
-                return Ok(bytes);
-            }
-        }
-    }
+    let images = generate_images_for_file(file, options)?;
+    let numbered = images.iter().any(|image| image.view > 0);
 
-    let possible_mesh = parse_mesh::parse_file(
-        absolute_path
+    for image in images {
+        let mut iter_file_path = image_path.with_extension(&image.extension);
+        let mut new_name = iter_file_path
+            .file_stem()
+            .unwrap()
             .to_str()
-            .ok_or_else(|| ThumbnailError::Other(String::from("Invalid path encoding")))?,
-    );
+            .unwrap()
+            .to_string();
 
-    match possible_mesh {
-        Ok(parse_result) => render_parse_result_to_bytes(
-            context,
-            viewport,
-            texture,
-            depth_texture,
-            &parse_result,
-            alpha,
-            filename,
-            &options.color,
-            options.rotatex,
-            options.rotatey,
-            options.inverse_zoom,
-            &options.format,
-        ),
-        Err(e) => {
-            if options.fallback_3mf_thumbnail
-                && filename.ends_with(".3mf")
-                && !options.prefer_3mf_thumbnail
-            {
-                if let Ok(bytes) = extract_image_from_3mf_to_bytes(
-                    &absolute_path,
-                    options.width,
-                    options.height,
-                    &options.format,
-                ) {
-                    return Ok(bytes);
-                }
-            }
-            Err(ThumbnailError::Parse(e.to_string()))
+        if numbered {
+            new_name = format!("{}-{:02}", new_name, image.view);
         }
+
+        if image.kind != ImageKind::Color {
+            new_name = format!("{}-{}", new_name, image.kind.to_string());
+        }
+
+        replace_file_stem(&mut iter_file_path, &new_name);
+
+        std::fs::write(iter_file_path, image.data)?;
     }
+
+    Ok(())
 }
 
+/// Generates a single thumbnail of `file`, or with an animation all of its frames in one image.
 pub fn generate_thumbnail_bytes_for_file(
     file: &Path,
     options: &ThumbnailOptions,
 ) -> Result<Vec<u8>, ThumbnailError> {
     let mut options = options.clone();
-    options.images_per_file = 1;
+    // Bytes hold a single image, unless all views go into one animation
+    if options.animation.is_none() {
+        options.images_per_file = 1;
+    }
 
-    let viewport = Viewport::new_at_origo(options.width, options.height);
-    let context = HeadlessContext::new().map_err(|e| ThumbnailError::Other(e.to_string()))?;
-    let alpha = if options.format == Format::Jpg {
-        0.8
-    } else {
-        0.0
-    };
+    generate_images_for_file(file, &options)?
+        .into_iter()
+        .next()
+        .map(|image| image.data)
+        .ok_or_else(|| ThumbnailError::Other(String::from("No image was generated")))
+}
 
-    let mut texture = Texture2D::new_empty::<[u8; 4]>(
-        &context,
-        viewport.width,
-        viewport.height,
-        Interpolation::Nearest,
-        Interpolation::Nearest,
-        None,
-        Wrapping::ClampToEdge,
-        Wrapping::ClampToEdge,
-    );
+///
+/// Generates every image `options` asks for from `file`, either rendered from the model or taken from a thumbnail embedded in it.
+/// This is the pipeline behind both the file and the bytes output.
+///
+pub fn generate_images_for_file(
+    file: &Path,
+    options: &ThumbnailOptions,
+) -> Result<Vec<EncodedImage>, ThumbnailError> {
+    let options = normalize_options(options);
+    let mut renderer = Renderer::new(&options)?;
+    generate_images_with_renderer(&mut renderer, file, &options)
+}
 
-    let mut depth_texture = DepthTexture2D::new::<f32>(
-        &context,
-        viewport.width,
-        viewport.height,
-        Wrapping::ClampToEdge,
-        Wrapping::ClampToEdge,
-    );
+fn normalize_options(options: &ThumbnailOptions) -> ThumbnailOptions {
+    let mut options = options.clone();
 
-    generate_thumbnail_bytes_for_file_with_context(
-        &context,
-        &viewport,
-        file,
-        alpha,
-        &mut texture,
-        &mut depth_texture,
-        &options,
-    )
+    if options.images_per_file < 1 {
+        options.images_per_file = 1;
+    }
+
+    if view_count(&options) > 1 && options.rotatex != 0.0 {
+        options.rotatex = 0.0;
+    }
+
+    apply_view_preset(&mut options);
+    options
 }
 
-fn generate_thumbnail_for_file_with_context(
-    context: &HeadlessContext,
-    viewport: &Viewport,
-    file: &Path,
-    outdir: &Path,
-    alpha: f32,
-    texture: &mut Texture2D,
-    depth_texture: &mut DepthTexture2D,
-    options: &ThumbnailOptions,
-) -> Result<(), ThumbnailError> {
-    let absolute_path = path::absolute(file)?;
-    let filename = absolute_path
+/// Name of the image generated for `file`, the model extension replaced by the one of the output.
+fn image_file_name(file: &Path, options: &ThumbnailOptions) -> Result<String, ThumbnailError> {
+    let filename = file
         .file_name()
         .and_then(|s| s.to_str())
         .ok_or_else(|| ThumbnailError::Other(String::from("Invalid filename")))?;
 
-    let mut extension = absolute_path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("");
+    let mut extension = file.extension().and_then(|e| e.to_str()).unwrap_or("");
 
     if filename.ends_with(".stl.zip") {
         extension = "stl.zip";
@@ -326,109 +727,358 @@ fn generate_thumbnail_for_file_with_context(
         extension = "gcode.zip";
     }
 
-    let filename_image = format!(
+    let image_extension = match options.animation {
+        Some(animation) => animation.extension().to_string(),
+        None => options.format.to_string(),
+    };
+
+    Ok(format!(
         "{}{}",
         &filename[..filename.len() - extension.len()],
-        options.format.to_string()
-    );
-
-    let image_path = outdir.join(filename_image);
+        image_extension
+    ))
+}
 
-    if !options.overwrite && image_path.exists() {
-        return Ok(());
-    }
+fn generate_images_with_renderer(
+    renderer: &mut Renderer,
+    file: &Path,
+    options: &ThumbnailOptions,
+) -> Result<Vec<EncodedImage>, ThumbnailError> {
+    let absolute_path = path::absolute(file)?;
+    let filename = absolute_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| ThumbnailError::Other(String::from("Invalid filename")))?;
 
     if options.prefer_3mf_thumbnail && filename.ends_with(".3mf") {
-        if extract_image_from_3mf(&absolute_path, options.width, options.height, &image_path)
-            .is_ok()
-        {
-            return Ok(());
+        if let Ok(image) = load_thumbnail_from_3mf(&absolute_path, options) {
+            return Ok(vec![encode_embedded_image(
+                image,
+                ImageSource::Embedded3mf,
+                options,
+            )?]);
         }
     }
 
     if options.prefer_gcode_thumbnail {
-        if filename.ends_with(".gcode") {
-            if extract_image_from_gcode_file(
-                &absolute_path,
-                options.width,
-                options.height,
-                &image_path,
-            )
-            .is_ok()
-            {
-                return Ok(());
-            }
+        let embedded = if filename.ends_with(".gcode") {
+            load_thumbnail_from_gcode_file(&absolute_path, options).ok()
         } else if filename.ends_with(".gcode.zip") {
-            if extract_image_from_gcode_zip(
-                &absolute_path,
-                options.width,
-                options.height,
-                &image_path,
-            )
-            .is_ok()
-            {
-                return Ok(());
-            }
+            load_thumbnail_from_gcode_zip(&absolute_path, options).ok()
+        } else {
+            None
+        };
+
+        if let Some(image) = embedded {
+            return Ok(vec![encode_embedded_image(
+                image,
+                ImageSource::EmbeddedGcode,
+                options,
+            )?]);
         }
     }
 
-    let possible_mesh = parse_mesh::parse_file(
+    let possible_mesh = parse_mesh::parse_file_with_normals(
         absolute_path
             .to_str()
             .ok_or_else(|| ThumbnailError::Other(String::from("Invalid path encoding")))?,
+        options.smooth_normals,
     );
 
     match possible_mesh {
-        Ok(parse_result) => {
-            render_model(
-                context,
-                viewport,
-                texture,
-                depth_texture,
-                &parse_result,
-                alpha,
-                filename,
-                &image_path,
-                &options.color,
-                options.rotatex,
-                options.rotatey,
-                options.images_per_file,
-                options.inverse_zoom,
-            );
-            Ok(())
-        }
+        Ok(parse_result) => render_images(renderer, &parse_result, filename, options),
         Err(e) => {
             if options.fallback_3mf_thumbnail
                 && filename.ends_with(".3mf")
                 && !options.prefer_3mf_thumbnail
             {
-                if extract_image_from_3mf(
-                    &absolute_path,
-                    options.width,
-                    options.height,
-                    &image_path,
-                )
-                .is_err()
-                {
-                    return Err(ThumbnailError::Parse(e.to_string()));
+                if let Ok(image) = load_thumbnail_from_3mf(&absolute_path, options) {
+                    return Ok(vec![encode_embedded_image(
+                        image,
+                        ImageSource::Embedded3mf,
+                        options,
+                    )?]);
                 }
-                Ok(())
-            } else {
-                Err(ThumbnailError::Parse(e.to_string()))
             }
+            Err(ThumbnailError::Parse(e.to_string()))
         }
     }
 }
 
-fn viewport_from_texture(texture: &Texture2D) -> Viewport {
-    Viewport::new_at_origo(texture.width(), texture.height())
-}
-
+/// Renders the views of a parsed model and encodes them, all frames go into one image for animations.
+fn render_images(
+    renderer: &mut Renderer,
+    parse_result: &parse_mesh::ParseResult,
+    file: &str,
+    options: &ThumbnailOptions,
+) -> Result<Vec<EncodedImage>, ThumbnailError> {
+    let frames = renderer.render_frames(parse_result, file, options)?;
+    let (width, height) = output_size(options);
+
+    if let Some(animation) = options.animation {
+        return Ok(vec![EncodedImage {
+            data: encode_animation_frames(frames, animation, options)?,
+            width,
+            height,
+            extension: animation.extension().to_string(),
+            view: 0,
+            source: ImageSource::Rendered,
+            kind: ImageKind::Color,
+        }]);
+    }
+
+    let mut images = Vec::new();
+
+    for (view, frame) in frames.into_iter().enumerate() {
+        let view = view as u32;
+
+        images.push(EncodedImage {
+            data: encode_pixels(frame.pixels, width, height, options)?,
+            width,
+            height,
+            extension: options.format.to_string(),
+            view,
+            source: ImageSource::Rendered,
+            kind: ImageKind::Color,
+        });
+
+        let Some(maps) = frame.maps else {
+            continue;
+        };
+
+        // Maps are always PNG, lossy formats would corrupt the depths and normals
+        if options.depth_map {
+            images.push(EncodedImage {
+                data: png_output::encode_gray16(&maps.depth, options.png_compression)?,
+                width,
+                height,
+                extension: String::from("png"),
+                view,
+                source: ImageSource::Rendered,
+                kind: ImageKind::Depth,
+            });
+        }
+
+        if options.normal_map {
+            images.push(EncodedImage {
+                data: png_output::encode_png(&maps.normal, options.png_compression, None)?,
+                width,
+                height,
+                extension: String::from("png"),
+                view,
+                source: ImageSource::Rendered,
+                kind: ImageKind::Normal,
+            });
+        }
+    }
+
+    Ok(images)
+}
+
+fn encode_embedded_image(
+    image: DynamicImage,
+    source: ImageSource,
+    options: &ThumbnailOptions,
+) -> Result<EncodedImage, ThumbnailError> {
+    let resized = resize_dynamic_image(image, options);
+
+    Ok(EncodedImage {
+        width: resized.width(),
+        height: resized.height(),
+        data: encode_dynamic_image(resized, options)?,
+        extension: options.format.to_string(),
+        view: 0,
+        source,
+        kind: ImageKind::Color,
+    })
+}
+
+///
+/// Renders every model among `paths` into one overview image with the file name under each tile and writes it to `output`.
+/// Directories are searched recursively and zip archives holding several models are extracted to a temporary folder first.
+///
+pub fn generate_overview_for_paths(
+    paths: &[PathBuf],
+    output: &Path,
+    options: &ThumbnailOptions,
+) -> Result<(), ThumbnailError> {
+    if !options.overwrite && output.exists() {
+        return Ok(());
+    }
+
+    std::fs::write(output, generate_overview_bytes_for_paths(paths, options)?)?;
+    Ok(())
+}
+
+pub fn generate_overview_bytes_for_paths(
+    paths: &[PathBuf],
+    options: &ThumbnailOptions,
+) -> Result<Vec<u8>, ThumbnailError> {
+    let inputs = overview::collect_inputs(paths)?;
+
+    if inputs.files.is_empty() {
+        return Err(ThumbnailError::Other(String::from(
+            "No supported model files found",
+        )));
+    }
+
+    // Every tile is a single still view, rendered at the tile size
+    let mut options = options.clone();
+    options.width = options.tile_size.max(1);
+    options.height = options.width;
+    options.images_per_file = 1;
+    options.animation = None;
+    options.contact_sheet = Vec::new();
+    apply_view_preset(&mut options);
+
+    let mut renderer = Renderer::new(&options)?;
+    let background = background_rgba(&options.format);
+    // Files that fail to render keep an empty tile, so the overview still lists them
+    let mut failures = 0;
+    let tiles: Vec<Vec<[u8; 4]>> = inputs
+        .files
+        .iter()
+        .map(|(path, _)| {
+            render_overview_tile(&mut renderer, path, &options).unwrap_or_else(|e| {
+                eprintln!("Error while converting {}: {:?}.", path.display(), e);
+                failures += 1;
+                vec![background; (options.width * options.height) as usize]
+            })
+        })
+        .collect();
+
+    if failures == inputs.files.len() {
+        return Err(ThumbnailError::Other(String::from(
+            "None of the model files could be rendered",
+        )));
+    }
+    let labels: Vec<String> = inputs
+        .files
+        .iter()
+        .map(|(_, label)| label.clone())
+        .collect();
+
+    let (pixels, width, height) = overview::compose_overview(
+        tiles,
+        &labels,
+        options.width,
+        options.overview_columns,
+        background,
+    );
+
+    encode_pixels(pixels, width, height, &options)
+}
+
+fn render_overview_tile(
+    renderer: &mut Renderer,
+    path: &Path,
+    options: &ThumbnailOptions,
+) -> Result<Vec<[u8; 4]>, ThumbnailError> {
+    let filename = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| ThumbnailError::Other(String::from("Invalid filename")))?;
+    let parse_result = parse_mesh::parse_file_with_normals(
+        path.to_str()
+            .ok_or_else(|| ThumbnailError::Other(String::from("Invalid path encoding")))?,
+        options.smooth_normals,
+    )?;
+
+    let mut frames = renderer.render_frames(&parse_result, filename, options)?;
+
+    Ok(frames.swap_remove(0).pixels)
+}
+
+fn apply_view_preset(options: &mut ThumbnailOptions) {
+    if let Some(view) = &options.view {
+        (options.rotatex, options.rotatey) = view.angles();
+    }
+}
+
+/// Render viewport, enlarged by the antialias factor so the result can be downsampled afterwards.
+fn supersampled_viewport(context: &Context, options: &ThumbnailOptions) -> Viewport {
+    // Querying a limit of the context has no preconditions
+    let max_texture_size =
+        unsafe { context.get_parameter_i32(three_d::context::MAX_TEXTURE_SIZE) }.max(0) as u32;
+    let factor = antialias_factor(
+        options.antialias,
+        options.width.max(options.height),
+        max_texture_size,
+    );
+
+    Viewport::new_at_origo(options.width * factor, options.height * factor)
+}
+
+///
+/// Largest supersampling factor out of 1, 2, 4 and 8 that does not exceed `antialias`.
+/// Factors that would make the longer image side `size` exceed the maximum texture size of the context are skipped.
+///
+fn antialias_factor(antialias: u32, size: u32, max_texture_size: u32) -> u32 {
+    [8, 4, 2]
+        .into_iter()
+        .find(|factor| {
+            *factor <= antialias
+                && size
+                    .checked_mul(*factor)
+                    .is_some_and(|scaled| scaled <= max_texture_size)
+        })
+        .unwrap_or(1)
+}
+
+fn viewport_from_texture(texture: &Texture2D) -> Viewport {
+    Viewport::new_at_origo(texture.width(), texture.height())
+}
+
 fn parse_hex_color(s: &str) -> Result<u32, std::num::ParseIntError> {
     u32::from_str_radix(s, 16)
 }
 
-fn render_model(
+/// Deterministic color for a file name, an FNV-1a hash of the name picks the hue.
+fn file_name_color(file: &str) -> Srgba {
+    let hash = file.bytes().fold(0x811C9DC5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+    let hue = (hash % 360) as f32;
+
+    // HSV to RGB with a fixed saturation and value, so every hue is equally readable
+    let (saturation, value) = (0.55, 0.85);
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+
+    Srgba::new_opaque(
+        ((r + m) * 255.0).round() as u8,
+        ((g + m) * 255.0).round() as u8,
+        ((b + m) * 255.0).round() as u8,
+    )
+}
+
+/// Opaque color from a hex string, using `fallback` when the string is not valid hex.
+fn hex_to_srgba(s: &str, fallback: u32) -> Srgba {
+    rgb_to_srgba(parse_hex_color(s).unwrap_or(fallback))
+}
+
+fn rgb_to_srgba(color: u32) -> Srgba {
+    Srgba::new_opaque(
+        (color >> 16 & 0xFF) as u8,
+        (color >> 8 & 0xFF) as u8,
+        (color & 0xFF) as u8,
+    )
+}
+
+///
+/// Renders every view of the turntable, each downsampled, cropped and annotated to the output size.
+/// With a contact sheet every frame holds all of its views, turned together by the turntable angle.
+///
+fn render_frames(
     context: &HeadlessContext,
     viewport: &Viewport,
     texture: &mut Texture2D,
@@ -436,230 +1086,1278 @@ fn render_model(
     parse_result: &parse_mesh::ParseResult,
     alpha: f32,
     file: &str,
-    image_path: &PathBuf,
-    color: &str,
-    rotatex: f32,
-    rotatey: f32,
-    count: u32,
-    scale: f32,
-) {
-    let mut models = build_models(context, parse_result, color);
-    let width = texture.width();
-    let height = texture.height();
-
-    for iter in 0..count {
-        let mut iter_file_path = image_path.clone();
-        let mut local_rotatex = rotatex;
-
-        if count > 1 {
-            let new_name = format!(
-                "{}-{:02}",
-                iter_file_path.file_stem().unwrap().to_str().unwrap(),
-                iter
+    options: &ThumbnailOptions,
+) -> Result<Vec<RenderedFrame>, ThumbnailError> {
+    let prepared = prepare_parse_result(parse_result, file, options);
+    let parse_result = prepared.as_ref().unwrap_or(parse_result);
+
+    let mut models = build_models(context, parse_result, file, options);
+    let mut plate = build_plate(context, parse_result, file, options);
+    let width = options.width;
+    let height = options.height;
+    let count = view_count(options);
+    let views: Vec<(f32, f32)> = if options.contact_sheet.is_empty() {
+        vec![(options.rotatex, options.rotatey)]
+    } else {
+        options
+            .contact_sheet
+            .iter()
+            .map(ViewPreset::angles)
+            .collect()
+    };
+
+    let frames = (0..count)
+        .map(|iter| {
+            let turn = (360.0 / count as f32) * iter as f32;
+
+            views
+                .iter()
+                .map(|(rotatex, rotatey)| {
+                    let (pixels, dimensions, maps) = render_pixels_for_view(
+                        &mut models,
+                        plate.as_mut(),
+                        parse_result,
+                        file,
+                        viewport,
+                        texture,
+                        depth_texture,
+                        alpha,
+                        (rotatex + turn, *rotatey),
+                        options,
+                    );
+                    let pixels =
+                        downsample_pixels(pixels, texture.width(), texture.height(), options)?;
+                    let maps = maps.map(|maps| maps.resize(width, height));
+
+                    Ok((pixels, dimensions, maps))
+                })
+                .collect::<Result<Vec<_>, ThumbnailError>>()
+        })
+        .collect::<Result<Vec<_>, ThumbnailError>>()?;
+
+    // Animations crop every frame of a view to the same rectangle, otherwise the model jumps and pulses as it turns
+    let background = background_rgba(&options.format);
+    let shared_bounds: Vec<Option<(u32, u32, u32, u32)>> = (0..views.len())
+        .map(|view| {
+            if !options.auto_crop || options.animation.is_none() {
+                return None;
+            }
+
+            frames
+                .iter()
+                .filter_map(|cells| {
+                    let raw = cells[view].0.as_flattened().to_vec();
+                    RgbaImage::from_vec(width, height, raw)
+                })
+                .map(|image| content_bounds(&image, background))
+                .reduce(union_bounds)
+        })
+        .collect();
+
+    frames
+        .into_iter()
+        .map(|cells| {
+            let mut cells = cells
+                .into_iter()
+                .zip(&shared_bounds)
+                .map(|((pixels, dimensions, maps), bounds)| {
+                    let (pixels, maps) =
+                        auto_crop_pixels(pixels, maps, width, height, *bounds, options)?;
+
+                    Ok(RenderedFrame {
+                        pixels: annotate_pixels(pixels, width, height, dimensions, options),
+                        maps,
+                    })
+                })
+                .collect::<Result<Vec<RenderedFrame>, ThumbnailError>>()?;
+
+            if options.contact_sheet.is_empty() {
+                return Ok(cells.swap_remove(0));
+            }
+
+            let labels: Option<Vec<String>> = options.sheet_labels.then(|| {
+                options
+                    .contact_sheet
+                    .iter()
+                    .map(ViewPreset::label)
+                    .collect()
+            });
+
+            Ok(RenderedFrame {
+                pixels: contact_sheet::compose_sheet(
+                    cells.into_iter().map(|cell| cell.pixels).collect(),
+                    labels.as_deref(),
+                    (width, height),
+                    options.sheet_columns,
+                    background,
+                ),
+                maps: None,
+            })
+        })
+        .collect()
+}
+
+/// The pixels of one frame at the output size, with the surface maps of its view when they were requested.
+struct RenderedFrame {
+    pixels: Vec<[u8; 4]>,
+    maps: Option<SurfaceMaps>,
+}
+
+/// Depth and normal maps belong to a single view, animations and contact sheets do not get them.
+fn surface_maps_requested(options: &ThumbnailOptions) -> bool {
+    (options.depth_map || options.normal_map)
+        && options.animation.is_none()
+        && options.contact_sheet.is_empty()
+}
+
+///
+/// Copy of `parse_result` scaled to millimeters and, with auto orientation, rotated so the model rests on a stable face of its convex hull.
+/// Returns `None` when the meshes can be used as they are.
+/// G-code is already laid out the way it is printed and keeps its orientation.
+///
+fn prepare_parse_result(
+    parse_result: &parse_mesh::ParseResult,
+    file: &str,
+    options: &ThumbnailOptions,
+) -> Option<parse_mesh::ParseResult> {
+    let gcode = model_frame(file, options) == ModelFrame::GCode;
+    // G-code moves are always millimeters
+    let scale = if gcode {
+        1.0
+    } else {
+        options
+            .units
+            .millimeters()
+            .or(parse_result.unit_scale())
+            .unwrap_or(1.0)
+    };
+    let orient = options.auto_orient.filter(|_| !gcode);
+
+    if scale == 1.0 && orient.is_none() {
+        return None;
+    }
+
+    let scaled: Vec<MeshWithTransform> = parse_result
+        .meshes
+        .iter()
+        .map(|mesh_with_transform| MeshWithTransform {
+            mesh: mesh_with_transform.mesh.clone(),
+            transform: Mat4::from_scale(scale) * mesh_with_transform.transform,
+            color: mesh_with_transform.color,
+        })
+        .collect();
+
+    let rotation = match orient {
+        Some(strategy) => {
+            let down = (print_to_model(file, options) * vec4(0.0, 0.0, -1.0, 0.0)).truncate();
+            orientation::resting_rotation(&scaled, down, strategy)
+        }
+        None => Mat4::identity(),
+    };
+
+    Some(
+        parse_mesh::ParseResult::multiple(
+            scaled
+                .into_iter()
+                .map(|mesh_with_transform| MeshWithTransform {
+                    transform: rotation * mesh_with_transform.transform,
+                    ..mesh_with_transform
+                })
+                .collect(),
+        )
+        .with_bed_shape(parse_result.bed_shape().map(<[Vec2]>::to_vec)),
+    )
+}
+
+/// Size of the encoded images, a contact sheet holds one cell of the requested size per view.
+fn output_size(options: &ThumbnailOptions) -> (u32, u32) {
+    if options.contact_sheet.is_empty() {
+        return (options.width, options.height);
+    }
+
+    contact_sheet::sheet_size(
+        options.contact_sheet.len(),
+        options.sheet_columns,
+        options.width,
+        options.height,
+    )
+}
+
+// Ten degrees per frame when an animation is requested without a frame count
+const DEFAULT_ANIMATION_FRAMES: u32 = 36;
+
+/// Number of views rendered per file, an animation without an explicit count gets a full turntable.
+fn view_count(options: &ThumbnailOptions) -> u32 {
+    match options.animation {
+        Some(_) if options.images_per_file <= 1 => DEFAULT_ANIMATION_FRAMES,
+        _ => options.images_per_file.max(1),
+    }
+}
+
+fn encode_animation_frames(
+    frames: Vec<RenderedFrame>,
+    animation: AnimationFormat,
+    options: &ThumbnailOptions,
+) -> Result<Vec<u8>, ThumbnailError> {
+    let (width, height) = output_size(options);
+    let images = frames
+        .into_iter()
+        .map(|frame| pixels_to_image(frame.pixels, width, height))
+        .collect::<Result<Vec<RgbaImage>, ThumbnailError>>()?;
+
+    animation::encode_animation(
+        &images,
+        animation,
+        options.frame_rate,
+        options.loop_count,
+        options.gif_palette,
+    )
+}
+
+/// The GPU objects for a single [MeshWithTransform], depending on the render mode a surface, a wireframe or both.
+struct SceneModel {
+    surface: Option<Gm<Mesh, solid_material::SolidMaterial>>,
+    wireframe: Option<Gm<InstancedMesh, solid_material::SolidMaterial>>,
+}
+
+impl SceneModel {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        if let Some(surface) = &mut self.surface {
+            surface.set_transformation(transformation);
+        }
+        if let Some(wireframe) = &mut self.wireframe {
+            wireframe.set_transformation(transformation);
+        }
+    }
+
+    ///
+    /// Replaces every material by the one `update` builds from it.
+    /// The flag tells whether the material colors the model or draws an overlay on top of it.
+    ///
+    fn update_materials(
+        &mut self,
+        update: impl Fn(solid_material::SolidMaterial, bool) -> solid_material::SolidMaterial,
+    ) {
+        let has_surface = self.surface.is_some();
+        if let Some(surface) = &mut self.surface {
+            surface.material = update(std::mem::take(&mut surface.material), true);
+        }
+        if let Some(wireframe) = &mut self.wireframe {
+            wireframe.material = update(std::mem::take(&mut wireframe.material), !has_surface);
+        }
+    }
+
+    fn aabb(&self) -> AxisAlignedBoundingBox {
+        match (&self.surface, &self.wireframe) {
+            (Some(surface), _) => surface.aabb(),
+            (None, Some(wireframe)) => wireframe.aabb(),
+            (None, None) => AxisAlignedBoundingBox::EMPTY,
+        }
+    }
+
+    fn objects(&self) -> Vec<&dyn Object> {
+        let mut objects: Vec<&dyn Object> = Vec::new();
+        if let Some(surface) = &self.surface {
+            objects.push(surface);
+        }
+        if let Some(wireframe) = &self.wireframe {
+            objects.push(wireframe);
+        }
+        objects
+    }
+}
+
+/// Coordinate frame of the parsed meshes, from the file type and the up axis option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ModelFrame {
+    ZUp,
+    YUp,
+    /// parse_mesh stores G-code moves as (-x, z, y)
+    GCode,
+}
+
+fn model_frame(file: &str, options: &ThumbnailOptions) -> ModelFrame {
+    if file.ends_with("gcode") || file.ends_with("gcode.zip") {
+        return ModelFrame::GCode;
+    }
+
+    match options.up_axis {
+        UpAxis::Y => ModelFrame::YUp,
+        UpAxis::Z => ModelFrame::ZUp,
+        // Most modeling tools write OBJ files Y up, STL and 3MF are Z up like the printer
+        UpAxis::Auto if file.ends_with(".obj") || file.ends_with(".obj.zip") => ModelFrame::YUp,
+        UpAxis::Auto => ModelFrame::ZUp,
+    }
+}
+
+/// Maps print coordinates (Z up, millimeters on the bed) to the coordinates the parsed meshes use.
+fn print_to_model(file: &str, options: &ThumbnailOptions) -> Mat4 {
+    match model_frame(file, options) {
+        ModelFrame::ZUp => Mat4::identity(),
+        ModelFrame::YUp => Mat4::from_cols(
+            vec4(1.0, 0.0, 0.0, 0.0),
+            vec4(0.0, 0.0, -1.0, 0.0),
+            vec4(0.0, 1.0, 0.0, 0.0),
+            vec4(0.0, 0.0, 0.0, 1.0),
+        ),
+        ModelFrame::GCode => Mat4::from_cols(
+            vec4(-1.0, 0.0, 0.0, 0.0),
+            vec4(0.0, 0.0, 1.0, 0.0),
+            vec4(0.0, 1.0, 0.0, 0.0),
+            vec4(0.0, 0.0, 0.0, 1.0),
+        ),
+    }
+}
+
+/// Rotates the mesh coordinates into the scene, where Y points up and the front of the printer faces +Z.
+fn model_to_scene(file: &str, options: &ThumbnailOptions) -> Mat4 {
+    match model_frame(file, options) {
+        ModelFrame::ZUp => Mat4::from_angle_x(Deg(270.0)),
+        ModelFrame::YUp => Mat4::identity(),
+        ModelFrame::GCode => Mat4::from_angle_y(Deg(180.0)),
+    }
+}
+
+///
+/// Builds the build plate below the model, in print coordinates.
+/// The bed size comes from the options, then from the printer profile in the file, and otherwise covers the model footprint.
+/// The plate surface is returned as the surface and the grid lines as the wireframe of a [SceneModel].
+///
+fn build_plate(
+    context: &HeadlessContext,
+    parse_result: &parse_mesh::ParseResult,
+    file: &str,
+    options: &ThumbnailOptions,
+) -> Option<SceneModel> {
+    if !options.build_plate {
+        return None;
+    }
+
+    let model_to_print = print_to_model(file, options)
+        .invert()
+        .unwrap_or(Mat4::identity());
+    let mut min = vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+    let mut max = vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+
+    for mesh_with_transform in parse_result.meshes.iter() {
+        let aabb = mesh_with_transform.mesh.compute_aabb();
+        let transform = model_to_print * mesh_with_transform.transform;
+
+        for i in 0..8 {
+            let corner = vec3(
+                if i & 1 == 0 {
+                    aabb.min().x
+                } else {
+                    aabb.max().x
+                },
+                if i & 2 == 0 {
+                    aabb.min().y
+                } else {
+                    aabb.max().y
+                },
+                if i & 4 == 0 {
+                    aabb.min().z
+                } else {
+                    aabb.max().z
+                },
+            );
+            let corner = (transform * corner.extend(1.0)).truncate();
+
+            min = vec3(
+                min.x.min(corner.x),
+                min.y.min(corner.y),
+                min.z.min(corner.z),
+            );
+            max = vec3(
+                max.x.max(corner.x),
+                max.y.max(corner.y),
+                max.z.max(corner.z),
             );
-            replace_file_stem(&mut iter_file_path, &new_name);
         }
+    }
 
-        if iter > 0 {
-            local_rotatex += (360.0 / count as f32) * iter as f32;
+    if !min.x.is_finite() || !max.x.is_finite() {
+        return None;
+    }
+
+    let size = max - min;
+    let spacing = options.grid_spacing;
+    // Round up to whole grid cells, with one spare cell on every side
+    let cover = |extent: f32| {
+        if spacing > 0.0 {
+            ((extent / spacing).ceil() + 2.0) * spacing
+        } else {
+            extent * 1.2
         }
+    };
 
-        let pixels = render_pixels_for_view(
-            &mut models,
-            parse_result,
-            file,
-            viewport,
-            texture,
-            depth_texture,
-            alpha,
-            local_rotatex,
-            rotatey,
-            scale,
-        );
+    let outline = match (
+        options.bed_width,
+        options.bed_depth,
+        parse_result.bed_shape(),
+    ) {
+        (None, None, Some(bed_shape)) => bed_shape.to_vec(),
+        (width, depth, _) => {
+            let (auto_width, auto_depth) = match options.bed_shape {
+                BedShape::Rectangle => (cover(size.x), cover(size.y)),
+                BedShape::Circle => {
+                    let diameter = cover(size.x.hypot(size.y));
+                    (diameter, diameter)
+                }
+            };
 
-        save_pixels_to_path(pixels, width, height, &iter_file_path);
+            build_plate::bed_outline(
+                options.bed_shape,
+                vec2((min.x + max.x) / 2.0, (min.y + max.y) / 2.0),
+                width.or(depth).unwrap_or(auto_width),
+                depth.or(width).unwrap_or(auto_depth),
+            )
+        }
+    };
+
+    // Lines stay thin next to small models, but never thicker than a fraction of a grid cell
+    let mut radius = size.magnitude() * 0.002;
+    if spacing > 0.0 {
+        radius = radius.min(spacing * 0.04);
     }
+
+    // The grid lines end flush with the bottom of the model, the plate runs through their centers
+    let height = min.z - radius;
+    let material = |color| {
+        solid_material::SolidMaterial::new_opaque(
+            &context,
+            &CpuMaterial {
+                albedo: color,
+                ..Default::default()
+            },
+        )
+        .with_lighting(options.lighting)
+    };
+
+    Some(SceneModel {
+        surface: Some(Gm::new(
+            Mesh::new(&context, &build_plate::plate_mesh(&outline, height)),
+            material(Srgba::new_opaque(0x48, 0x48, 0x48)),
+        )),
+        wireframe: Some(Gm::new(
+            InstancedMesh::new(
+                &context,
+                &build_plate::grid_instances(&outline, spacing, height, radius),
+                &CpuMesh::cylinder(6),
+            ),
+            material(Srgba::new_opaque(0x80, 0x80, 0x80)),
+        )),
+    })
+}
+
+// Tableau 10, colors that stay distinguishable next to each other
+const PART_PALETTE: [u32; 10] = [
+    0x4E79A7, 0xF28E2B, 0xE15759, 0x76B7B2, 0x59A14F, 0xEDC948, 0xB07AA1, 0xFF9DA7, 0x9C755F,
+    0xBAB0AC,
+];
+
+/// Palette color of the part at `index`, starting over after the last color.
+fn part_color(index: usize) -> Srgba {
+    rgb_to_srgba(PART_PALETTE[index % PART_PALETTE.len()])
 }
 
 fn build_models(
     context: &HeadlessContext,
     parse_result: &parse_mesh::ParseResult,
-    color: &str,
-) -> Vec<Gm<Mesh, solid_material::SolidMaterial>> {
-    let default_color = parse_hex_color(color).unwrap();
-    let default_srgba = Srgba::new_opaque(
-        (default_color >> 16 & 0xFF) as u8,
-        (default_color >> 8 & 0xFF) as u8,
-        (default_color & 0xFF) as u8,
-    );
+    file: &str,
+    options: &ThumbnailOptions,
+) -> Vec<SceneModel> {
+    let default_srgba = match options.color_mode {
+        ColorMode::FileName => file_name_color(file),
+        _ => rgb_to_srgba(parse_hex_color(&options.color).unwrap()),
+    };
 
-    parse_result
+    // Wire thickness follows the size of the whole placed model so every part gets the same line weight
+    let (min, max) = parse_result
         .meshes
         .iter()
-        .map(|mesh_with_transform| {
-            let albedo = mesh_with_transform.color.unwrap_or(default_srgba);
+        .flat_map(|mesh_with_transform| {
+            let transform = mesh_with_transform.transform;
+            mesh_with_transform
+                .mesh
+                .positions
+                .to_f32()
+                .into_iter()
+                .map(move |position| (transform * position.extend(1.0)).truncate())
+        })
+        .fold(
+            (
+                vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+                vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+            ),
+            |(min, max), position| {
+                (
+                    vec3(
+                        min.x.min(position.x),
+                        min.y.min(position.y),
+                        min.z.min(position.z),
+                    ),
+                    vec3(
+                        max.x.max(position.x),
+                        max.y.max(position.y),
+                        max.z.max(position.z),
+                    ),
+                )
+            },
+        );
+    let wire_radius = if min.x <= max.x {
+        (max - min).magnitude() * 0.001
+    } else {
+        0.0
+    };
 
-            Gm::new(
-                Mesh::new(&context, &mesh_with_transform.mesh),
-                solid_material::SolidMaterial::new_opaque(
+    parse_result
+        .meshes
+        .iter()
+        .enumerate()
+        .map(|(index, mesh_with_transform)| {
+            let albedo = match options.color_mode {
+                ColorMode::Part => mesh_with_transform
+                    .color
+                    .unwrap_or_else(|| part_color(index)),
+                _ => mesh_with_transform.color.unwrap_or(default_srgba),
+            };
+            let material = |albedo| {
+                let mut material = solid_material::SolidMaterial::new_opaque(
                     &context,
                     &CpuMaterial {
                         albedo,
                         ..Default::default()
                     },
-                ),
-            )
+                )
+                .with_preset(options.material)
+                .with_lighting(options.lighting);
+
+                if let Some(ambient) = options.ambient {
+                    material.ambient = ambient;
+                }
+                if let Some(specular) = options.specular {
+                    material.specular = specular;
+                }
+                material
+            };
+
+            let surface = match options.render_mode {
+                RenderMode::Shaded | RenderMode::ShadedWireframe if options.smooth_normals => {
+                    // Normals from the file take precedence over computed ones
+                    let mesh = match mesh_with_transform.mesh.normals {
+                        Some(_) => mesh_with_transform.mesh.clone(),
+                        None => parse_mesh::compute_smooth_normals(
+                            &mesh_with_transform.mesh,
+                            options.smooth_angle,
+                        ),
+                    };
+
+                    Some(Gm::new(
+                        Mesh::new(&context, &mesh),
+                        material(albedo).with_smooth_normals(true),
+                    ))
+                }
+                RenderMode::Shaded | RenderMode::ShadedWireframe => Some(Gm::new(
+                    Mesh::new(&context, &mesh_with_transform.mesh),
+                    material(albedo),
+                )),
+                RenderMode::Wireframe | RenderMode::Points => None,
+            };
+
+            let wireframe = match options.render_mode {
+                RenderMode::Shaded => None,
+                RenderMode::Wireframe => Some(Gm::new(
+                    InstancedMesh::new(
+                        &context,
+                        &wireframe::edge_instances(
+                            &mesh_with_transform.mesh,
+                            mesh_with_transform.transform,
+                            wire_radius,
+                        ),
+                        &CpuMesh::cylinder(6),
+                    ),
+                    material(albedo),
+                )),
+                RenderMode::ShadedWireframe => Some(Gm::new(
+                    InstancedMesh::new(
+                        &context,
+                        &wireframe::edge_instances(
+                            &mesh_with_transform.mesh,
+                            mesh_with_transform.transform,
+                            wire_radius,
+                        ),
+                        &CpuMesh::cylinder(6),
+                    ),
+                    material(Srgba::new_opaque(0x20, 0x20, 0x20)),
+                )),
+                RenderMode::Points => Some(Gm::new(
+                    InstancedMesh::new(
+                        &context,
+                        &wireframe::vertex_instances(
+                            &mesh_with_transform.mesh,
+                            mesh_with_transform.transform,
+                            wire_radius * 3.0,
+                        ),
+                        &CpuMesh::sphere(6),
+                    ),
+                    material(albedo),
+                )),
+            };
+
+            SceneModel { surface, wireframe }
         })
         .collect()
 }
 
 fn render_pixels_for_view(
-    models: &mut [Gm<Mesh, solid_material::SolidMaterial>],
+    models: &mut [SceneModel],
+    plate: Option<&mut SceneModel>,
     parse_result: &parse_mesh::ParseResult,
     file: &str,
     viewport: &Viewport,
     texture: &mut Texture2D,
     depth_texture: &mut DepthTexture2D,
     alpha: f32,
-    rotatex: f32,
-    rotatey: f32,
-    scale: f32,
-) -> Vec<[u8; 4]> {
+    angles: (f32, f32),
+    options: &ThumbnailOptions,
+) -> (Vec<[u8; 4]>, Vec3, Option<SurfaceMaps>) {
+    for (idx, model) in models.iter_mut().enumerate() {
+        model.set_transformation(parse_result.meshes[idx].transform);
+    }
+
+    // Extents of the assembled model, the exploded view below only spreads it out
+    let assembled_size = {
+        let (min, max) = combined_aabb(models);
+        max - min
+    };
+    let transforms = explode_transforms(models, parse_result, file, options);
+
+    let (combined_min, combined_max) = combined_aabb(models);
+
+    let mut offset = Mat4::from_translation(combined_min * -1.0)
+        * Mat4::from_translation((combined_min - combined_max) / 2f32);
+
+    offset = model_to_scene(file, options) * offset;
+
+    for (model, transform) in models.iter_mut().zip(transforms.iter()) {
+        model.set_transformation(offset * *transform);
+    }
+
+    let scene_aabb = combined_aabb(models);
+    let camera = build_camera(viewport, scene_aabb, angles, options);
+
+    let clip_plane = section_plane(file, offset, scene_aabb, options);
+    let cap_color = hex_to_srgba(&options.section_color, 0xE0452C);
+    let overhangs = overhang_highlight(file, offset, scene_aabb, options);
+    let height_gradient = height_gradient(file, offset, scene_aabb, options);
+    for model in models.iter_mut() {
+        model.update_materials(|material, colors_model| {
+            let material = material.with_clip_plane(clip_plane, cap_color);
+            // The wireframe overlay keeps its own color
+            if colors_model {
+                material
+                    .with_overhangs(overhangs)
+                    .with_height_gradient(height_gradient)
+            } else {
+                material
+            }
+        });
+    }
+
+    let model_refs: Vec<&dyn Object> = models.iter().flat_map(|m| m.objects()).collect();
+
+    // The plate is left out of the framing and the shadow footprint, it only shows the scale
+    let plate = plate.map(|plate| {
+        plate.set_transformation(offset * print_to_model(file, options));
+        &*plate
+    });
+    let mut scene_refs = model_refs.clone();
+    if let Some(plate) = plate {
+        scene_refs.extend(plate.objects());
+    }
+
+    let footprint = if options.ground_shadow {
+        Some(render_footprint(
+            viewport,
+            texture,
+            depth_texture,
+            &model_refs,
+            scene_aabb,
+        ))
+    } else {
+        None
+    };
+
+    let render_target = RenderTarget::new(
+        texture.as_color_target(None),
+        depth_texture.as_depth_target(),
+    );
+    render_target
+        .clear(ClearState::color_and_depth(0.2, 0.2, 0.2, alpha, 1.0))
+        .render(&camera, &scene_refs, &[]);
+
+    let mut pixels = render_target.read_color();
+    let mut maps = None;
+
+    if options.outline
+        || options.ambient_occlusion
+        || footprint.is_some()
+        || surface_maps_requested(options)
+    {
+        let gbuffer = screen_space::GBuffer::new(
+            render_target.read_depth(),
+            viewport.width,
+            viewport.height,
+            &camera,
+        );
+
+        if surface_maps_requested(options) {
+            maps = Some(SurfaceMaps::new(&gbuffer, &camera, scene_aabb));
+        }
+
+        if let Some(footprint) = &footprint {
+            occlusion::apply_ground_shadow(
+                &mut pixels,
+                &gbuffer,
+                footprint,
+                scene_aabb.0.y,
+                options.shadow_strength,
+            );
+        }
+
+        if options.ambient_occlusion {
+            occlusion::apply_ambient_occlusion(
+                &mut pixels,
+                &gbuffer,
+                viewport.width.min(viewport.height) as f32 * 0.02,
+                options.ao_strength,
+            );
+        }
+
+        if options.outline {
+            let outline_color = parse_hex_color(&options.outline_color).unwrap_or(0);
+
+            outline::draw_outlines(
+                &mut pixels,
+                &gbuffer,
+                [
+                    (outline_color >> 16 & 0xFF) as u8,
+                    (outline_color >> 8 & 0xFF) as u8,
+                    (outline_color & 0xFF) as u8,
+                    255,
+                ],
+                options.outline_width * (viewport.width / options.width.max(1)) as f32,
+                options.crease_angle,
+            );
+        }
+    }
+
+    if options.dimension_lines {
+        annotation::draw_dimension_lines(
+            &mut pixels,
+            viewport.width as usize,
+            viewport.height as usize,
+            &camera,
+            scene_aabb,
+            options.dimension_units,
+        );
+    }
+
+    // The scene is rotated so the printer Y axis points along -Z and Z points up
+    let size = (offset * assembled_size.extend(0.0)).truncate();
+    (pixels, vec3(size.x.abs(), size.z.abs(), size.y.abs()), maps)
+}
+
+///
+/// Returns the section plane in scene coordinates as normal and distance from the origin.
+/// The normal is given in print coordinates and the plane passes `section_offset` millimeters along it from the center of the model.
+///
+fn section_plane(
+    file: &str,
+    offset: Mat4,
+    (scene_min, scene_max): (Vec3, Vec3),
+    options: &ThumbnailOptions,
+) -> Option<Vec4> {
+    let [x, y, z] = options
+        .section_normal
+        .or(options.section.map(|axis| axis.normal()))?;
+    let normal = vec3(x, y, z);
+
+    if normal.magnitude2() == 0.0 {
+        return None;
+    }
+
+    let normal = (offset * print_to_model(file, options) * normal.extend(0.0))
+        .truncate()
+        .normalize();
+    let point = (scene_min + scene_max) / 2.0 + normal * options.section_offset;
+
+    Some(normal.extend(normal.dot(point)))
+}
+
+/// Overhang coloring for the current view, with the build direction and the bed height in scene coordinates.
+fn overhang_highlight(
+    file: &str,
+    offset: Mat4,
+    (scene_min, scene_max): (Vec3, Vec3),
+    options: &ThumbnailOptions,
+) -> Option<OverhangHighlight> {
+    if !options.overhangs {
+        return None;
+    }
+
+    let up = (offset * print_to_model(file, options) * vec4(0.0, 0.0, 1.0, 0.0))
+        .truncate()
+        .normalize();
+    // Tolerance for faces that are meant to rest on the bed, relative to the model size
+    let tolerance = (scene_max - scene_min).magnitude() * 0.001;
+    let lowest = scene_min.dot(up).min(scene_max.dot(up));
+
+    Some(OverhangHighlight {
+        up,
+        bed_height: lowest + tolerance,
+        angle: options.overhang_angle,
+        overhang_color: hex_to_srgba(&options.overhang_color, 0xE02020),
+        bed_color: hex_to_srgba(&options.bed_contact_color, 0x2E7DD7),
+    })
+}
+
+/// Height gradient for the current view, spanning the whole scene along the build direction.
+fn height_gradient(
+    file: &str,
+    offset: Mat4,
+    (scene_min, scene_max): (Vec3, Vec3),
+    options: &ThumbnailOptions,
+) -> Option<HeightGradient> {
+    if options.color_mode != ColorMode::Height {
+        return None;
+    }
+
+    let up = (offset * print_to_model(file, options) * vec4(0.0, 0.0, 1.0, 0.0))
+        .truncate()
+        .normalize();
+
+    Some(HeightGradient {
+        up,
+        low: scene_min.dot(up).min(scene_max.dot(up)),
+        high: scene_min.dot(up).max(scene_max.dot(up)),
+    })
+}
+
+///
+/// Moves every part away from the center of the assembly by the explode factor times the distance of its center, and applies the result to `models`.
+/// The exploded assembly is lifted back so its lowest point stays where it was, keeping parts above the build plate.
+/// Expects `models` to carry the plain mesh transforms and returns the exploded ones.
+///
+fn explode_transforms(
+    models: &mut [SceneModel],
+    parse_result: &parse_mesh::ParseResult,
+    file: &str,
+    options: &ThumbnailOptions,
+) -> Vec<Mat4> {
+    let transforms: Vec<Mat4> = parse_result.meshes.iter().map(|m| m.transform).collect();
+    let factor = options.explode;
+
+    if factor <= 0.0 || models.len() < 2 {
+        return transforms;
+    }
+
+    let (min, max) = combined_aabb(models);
+    let center = (min + max) / 2.0;
+
+    let displacements: Vec<Vec3> = models
+        .iter()
+        .map(|model| {
+            let aabb = model.aabb();
+            if aabb.min().x.is_finite() {
+                ((aabb.min() + aabb.max()) / 2.0 - center) * factor
+            } else {
+                vec3(0.0, 0.0, 0.0)
+            }
+        })
+        .collect();
+
+    for ((model, transform), displacement) in models
+        .iter_mut()
+        .zip(transforms.iter())
+        .zip(displacements.iter())
+    {
+        model.set_transformation(Mat4::from_translation(*displacement) * *transform);
+    }
+
+    // Print Z is a positive axis of the model coordinates, so its lowest value is the minimum along it
+    let up = (print_to_model(file, options) * vec4(0.0, 0.0, 1.0, 0.0)).truncate();
+    let (exploded_min, _) = combined_aabb(models);
+    let lift = up * (min.dot(up) - exploded_min.dot(up));
+
+    transforms
+        .iter()
+        .zip(displacements.iter())
+        .zip(models.iter_mut())
+        .map(|((transform, displacement), model)| {
+            let transform = Mat4::from_translation(*displacement + lift) * *transform;
+            model.set_transformation(transform);
+            transform
+        })
+        .collect()
+}
+
+/// Renders the scene from straight above and turns its coverage into a blurred ground shadow.
+fn render_footprint(
+    viewport: &Viewport,
+    texture: &mut Texture2D,
+    depth_texture: &mut DepthTexture2D,
+    objects: &[&dyn Object],
+    (scene_min, scene_max): (Vec3, Vec3),
+) -> occlusion::Footprint {
+    let center = (scene_min + scene_max) / 2.0;
+    let size = scene_max - scene_min;
+    let blur = size.x.max(size.z) * 0.08;
+    let aspect = viewport.width as f32 / viewport.height as f32;
+    let height = (size.z + 4.0 * blur).max((size.x + 4.0 * blur) / aspect);
+
+    let camera = Camera::new_orthographic(
+        viewport.clone(),
+        vec3(center.x, scene_max.y + size.y + 1.0, center.z),
+        vec3(center.x, scene_min.y, center.z),
+        vec3(0.0, 0.0, -1.0),
+        height,
+        0.5,
+        2.0 * size.y + 2.0,
+    );
+
+    let depth = RenderTarget::new(
+        texture.as_color_target(None),
+        depth_texture.as_depth_target(),
+    )
+    .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0))
+    .render(&camera, objects, &[])
+    .read_depth();
+
+    let pixels_per_unit = viewport.height as f32 / height;
+
+    occlusion::Footprint::new(
+        &depth,
+        viewport.width,
+        viewport.height,
+        &camera,
+        (blur * pixels_per_unit).round().max(1.0) as usize,
+    )
+}
+
+fn combined_aabb(models: &[SceneModel]) -> (Vec3, Vec3) {
     let mut combined_min = vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY);
     let mut combined_max = vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
 
-    for (idx, model) in models.iter_mut().enumerate() {
-        let mesh_transform = parse_result.meshes[idx].transform;
-        model.set_transformation(mesh_transform);
-
+    for model in models.iter() {
         let aabb = model.aabb();
         combined_min = vec3(
             combined_min.x.min(aabb.min().x),
             combined_min.y.min(aabb.min().y),
             combined_min.z.min(aabb.min().z),
         );
-        combined_max = vec3(
-            combined_max.x.max(aabb.max().x),
-            combined_max.y.max(aabb.max().y),
-            combined_max.z.max(aabb.max().z),
-        );
-    }
-
-    let mut offset = Mat4::from_translation(combined_min * -1.0)
-        * Mat4::from_translation((combined_min - combined_max) / 2f32);
-
-    if file.ends_with(".stl")
-        || file.ends_with(".stl.zip")
-        || file.ends_with(".3mf")
-        || file.ends_with(".obj")
-        || file.ends_with(".obj.zip")
-    {
-        offset = Mat4::from_angle_x(Deg(270.0)) * offset;
-    } else if file.ends_with("gcode") || file.ends_with("gcode.zip") {
-        offset = Mat4::from_angle_y(Deg(180.0)) * offset;
+        combined_max = vec3(
+            combined_max.x.max(aabb.max().x),
+            combined_max.y.max(aabb.max().y),
+            combined_max.z.max(aabb.max().z),
+        );
     }
 
-    for (idx, model) in models.iter_mut().enumerate() {
-        let mesh_transform = parse_result.meshes[idx].transform;
-        model.set_transformation(offset * mesh_transform);
-    }
+    (combined_min, combined_max)
+}
 
-    let magnitude = (combined_min - combined_max).magnitude() * scale;
+fn build_camera(
+    viewport: &Viewport,
+    (combined_min, combined_max): (Vec3, Vec3),
+    (rotatex, rotatey): (f32, f32),
+    options: &ThumbnailOptions,
+) -> Camera {
+    let diagonal = (combined_min - combined_max).magnitude();
+    let magnitude = diagonal * options.inverse_zoom;
 
     let pitch = rotatey.clamp(-90.0, 90.0).to_radians();
     let yaw = rotatex.to_radians();
 
-    let x = magnitude * pitch.cos() * yaw.sin();
-    let y = magnitude * pitch.sin();
-    let z = magnitude * pitch.cos() * yaw.cos();
+    let direction = vec3(
+        pitch.cos() * yaw.sin(),
+        pitch.sin(),
+        pitch.cos() * yaw.cos(),
+    );
 
-    let camera = Camera::new_perspective(
-        viewport.clone(),
-        vec3(x, y, z),
-        vec3(0.0, 0.0, 0.0),
-        vec3(0.0, 1.0, 0.0),
-        degrees(45.0),
-        magnitude * 0.01,
-        1000.0,
+    // Up is the pitch derivative of the camera position, so top and bottom views stay well defined
+    let up = vec3(
+        -pitch.sin() * yaw.sin(),
+        pitch.cos(),
+        -pitch.sin() * yaw.cos(),
     );
 
-    let model_refs: Vec<&dyn Object> = models.iter().map(|m| m as &dyn Object).collect();
+    let half_fov_tan = 22.5f32.to_radians().tan();
+
+    if !options.auto_frame {
+        return match options.projection {
+            Projection::Perspective => Camera::new_perspective(
+                viewport.clone(),
+                direction * magnitude,
+                vec3(0.0, 0.0, 0.0),
+                up,
+                degrees(45.0),
+                magnitude * 0.01,
+                1000.0,
+            ),
+            // Match the visible height of the 45 degree perspective camera at the target
+            Projection::Orthographic => Camera::new_orthographic(
+                viewport.clone(),
+                direction * magnitude,
+                vec3(0.0, 0.0, 0.0),
+                up,
+                magnitude * 2.0 * half_fov_tan,
+                magnitude * 0.01,
+                1000.0,
+            ),
+        };
+    }
 
-    RenderTarget::new(
-        texture.as_color_target(None),
-        depth_texture.as_depth_target(),
-    )
-    .clear(ClearState::color_and_depth(0.2, 0.2, 0.2, alpha, 1.0))
-    .render(&camera, &model_refs, &[])
-    .read_color()
-}
+    // Project the bounding box corners onto the image plane of the chosen view
+    let right = up.cross(direction);
+    let corners: Vec<Vec3> = (0..8)
+        .map(|i| {
+            vec3(
+                if i & 1 == 0 {
+                    combined_min.x
+                } else {
+                    combined_max.x
+                },
+                if i & 2 == 0 {
+                    combined_min.y
+                } else {
+                    combined_max.y
+                },
+                if i & 4 == 0 {
+                    combined_min.z
+                } else {
+                    combined_max.z
+                },
+            )
+        })
+        .collect();
 
-fn save_pixels_to_path(pixels: Vec<[u8; 4]>, width: u32, height: u32, path: &Path) {
-    three_d_asset::io::save(
-        &CpuTexture {
-            data: TextureData::RgbaU8(pixels),
-            width,
-            height,
-            ..Default::default()
-        }
-        .serialize(path)
-        .unwrap(),
-    )
-    .unwrap();
-}
+    let (mut min_right, mut max_right) = (f32::INFINITY, f32::NEG_INFINITY);
+    let (mut min_up, mut max_up) = (f32::INFINITY, f32::NEG_INFINITY);
 
-fn render_parse_result_to_bytes(
-    context: &HeadlessContext,
-    viewport: &Viewport,
-    texture: &mut Texture2D,
-    depth_texture: &mut DepthTexture2D,
-    parse_result: &parse_mesh::ParseResult,
-    alpha: f32,
-    file: &str,
-    color: &str,
-    rotatex: f32,
-    rotatey: f32,
-    scale: f32,
-    format: &Format,
-) -> Result<Vec<u8>, ThumbnailError> {
-    let mut models = build_models(context, parse_result, color);
-    let pixels = render_pixels_for_view(
-        &mut models,
-        parse_result,
-        file,
-        viewport,
-        texture,
-        depth_texture,
-        alpha,
-        rotatex,
-        rotatey,
-        scale,
-    );
+    for corner in corners.iter() {
+        min_right = min_right.min(corner.dot(right));
+        max_right = max_right.max(corner.dot(right));
+        min_up = min_up.min(corner.dot(up));
+        max_up = max_up.max(corner.dot(up));
+    }
 
-    encode_pixels(pixels, texture.width(), texture.height(), format)
+    let target = right * (min_right + max_right) / 2.0 + up * (min_up + max_up) / 2.0;
+    let aspect = viewport.width as f32 / viewport.height as f32;
+    let fill = (1.0 - 2.0 * options.frame_margin / 100.0).max(0.01);
+
+    match options.projection {
+        Projection::Perspective => {
+            let tan_y = half_fov_tan * fill;
+            let tan_x = half_fov_tan * aspect * fill;
+
+            // Closest distance at which every corner is still inside the frustum
+            let distance = corners
+                .iter()
+                .map(|corner| {
+                    let relative = *corner - target;
+                    relative.dot(direction)
+                        + (relative.dot(right).abs() / tan_x).max(relative.dot(up).abs() / tan_y)
+                })
+                .fold(0.0f32, f32::max)
+                * options.inverse_zoom;
+
+            Camera::new_perspective(
+                viewport.clone(),
+                target + direction * distance,
+                target,
+                up,
+                degrees(45.0),
+                distance * 0.01,
+                distance + diagonal,
+            )
+        }
+        Projection::Orthographic => {
+            let height = (max_up - min_up).max((max_right - min_right) / aspect) / fill
+                * options.inverse_zoom;
+
+            Camera::new_orthographic(
+                viewport.clone(),
+                target + direction * diagonal,
+                target,
+                up,
+                height,
+                diagonal * 0.01,
+                diagonal * 2.0,
+            )
+        }
+    }
 }
 
 fn encode_pixels(
     pixels: Vec<[u8; 4]>,
     width: u32,
     height: u32,
-    format: &Format,
+    options: &ThumbnailOptions,
 ) -> Result<Vec<u8>, ThumbnailError> {
+    let image = pixels_to_image(pixels, width, height)?;
+    encode_dynamic_image(DynamicImage::ImageRgba8(image), options)
+}
+
+fn pixels_to_image(
+    pixels: Vec<[u8; 4]>,
+    width: u32,
+    height: u32,
+) -> Result<RgbaImage, ThumbnailError> {
     let mut raw = Vec::with_capacity((width * height * 4) as usize);
     for pixel in pixels {
         raw.extend_from_slice(&pixel);
     }
 
-    let image = RgbaImage::from_vec(width, height, raw)
-        .ok_or_else(|| ThumbnailError::Other(String::from("Failed to create image buffer")))?;
-    encode_dynamic_image(DynamicImage::ImageRgba8(image), format)
+    RgbaImage::from_vec(width, height, raw)
+        .ok_or_else(|| ThumbnailError::Other(String::from("Failed to create image buffer")))
+}
+
+/// Scales a supersampled render back down to the requested output size.
+fn downsample_pixels(
+    pixels: Vec<[u8; 4]>,
+    width: u32,
+    height: u32,
+    options: &ThumbnailOptions,
+) -> Result<Vec<[u8; 4]>, ThumbnailError> {
+    if width == options.width && height == options.height {
+        return Ok(pixels);
+    }
+
+    let image = pixels_to_image(pixels, width, height)?;
+
+    Ok(
+        imageops::resize(&image, options.width, options.height, Lanczos3)
+            .pixels()
+            .map(|pixel| pixel.0)
+            .collect(),
+    )
+}
+
+/// Draws the overlays that belong to the final image, after downsampling and cropping.
+fn annotate_pixels(
+    mut pixels: Vec<[u8; 4]>,
+    width: u32,
+    height: u32,
+    dimensions: Vec3,
+    options: &ThumbnailOptions,
+) -> Vec<[u8; 4]> {
+    if options.dimensions {
+        annotation::draw_dimension_caption(
+            &mut pixels,
+            width as usize,
+            height as usize,
+            dimensions,
+            options.dimension_units,
+        );
+    }
+
+    pixels
+}
+
+///
+/// Crops the pixels to the model and scales it back up to fill the image, applying the same crop to the surface maps.
+/// The crop rectangle is `bounds` when given, the bounding box of the model in these pixels otherwise.
+///
+fn auto_crop_pixels(
+    pixels: Vec<[u8; 4]>,
+    maps: Option<SurfaceMaps>,
+    width: u32,
+    height: u32,
+    bounds: Option<(u32, u32, u32, u32)>,
+    options: &ThumbnailOptions,
+) -> Result<(Vec<[u8; 4]>, Option<SurfaceMaps>), ThumbnailError> {
+    if !options.auto_crop {
+        return Ok((pixels, maps));
+    }
+
+    let background = background_rgba(&options.format);
+    let image = pixels_to_image(pixels, width, height)?;
+    let bounds = bounds.unwrap_or_else(|| content_bounds(&image, background));
+    let (x, y, crop_width, crop_height) = bounds;
+    let cropped = imageops::crop_imm(&image, x, y, crop_width, crop_height).to_image();
+
+    let pixels = fit_to_canvas(
+        &cropped,
+        width,
+        height,
+        options.crop_margin,
+        Rgba(background),
+        Triangle,
+    )
+    .pixels()
+    .map(|pixel| pixel.0)
+    .collect();
+    let maps = maps.map(|maps| maps.crop_to_canvas(bounds, width, height, options.crop_margin));
+
+    Ok((pixels, maps))
 }
 
-fn encode_dynamic_image(image: DynamicImage, format: &Format) -> Result<Vec<u8>, ThumbnailError> {
+// rav1e speed between 1 and 10, slower speeds barely shrink small thumbnails further
+const AVIF_SPEED: u8 = 7;
+
+fn encode_dynamic_image(
+    image: DynamicImage,
+    options: &ThumbnailOptions,
+) -> Result<Vec<u8>, ThumbnailError> {
     let mut buffer = Vec::new();
-    let output_format = match format {
-        Format::Png => ImageFormat::Png,
-        Format::Jpg => ImageFormat::Jpeg,
+    let image = if options.format.has_alpha() {
+        DynamicImage::ImageRgba8(image.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(image.to_rgb8())
+    };
+    let quality = options.quality.clamp(1, 100);
+
+    let output_format = match options.format {
+        Format::Png => {
+            return png_output::encode_png(
+                &image.to_rgba8(),
+                options.png_compression,
+                options.png_palette,
+            );
+        }
+        Format::Jpg => {
+            JpegEncoder::new_with_quality(&mut buffer, options.jpeg_quality.clamp(1, 100))
+                .write_image(
+                    image.as_bytes(),
+                    image.width(),
+                    image.height(),
+                    ExtendedColorType::Rgb8,
+                )
+                .map_err(|e| ThumbnailError::Other(e.to_string()))?;
+            return Ok(buffer);
+        }
+        Format::Bmp => ImageFormat::Bmp,
+        Format::Tiff => ImageFormat::Tiff,
+        Format::Qoi => ImageFormat::Qoi,
+        Format::Webp => {
+            // The image crate only writes lossless WebP, libwebp does both
+            let encoded = webp::Encoder::from_rgba(image.as_bytes(), image.width(), image.height())
+                .encode_simple(options.lossless, quality as f32)
+                .map_err(|e| ThumbnailError::Other(format!("WebP encoding failed: {e:?}")))?;
+            return Ok(encoded.to_vec());
+        }
+        Format::Avif => {
+            AvifEncoder::new_with_speed_quality(&mut buffer, AVIF_SPEED, quality)
+                .write_image(
+                    image.as_bytes(),
+                    image.width(),
+                    image.height(),
+                    ExtendedColorType::Rgba8,
+                )
+                .map_err(|e| ThumbnailError::Other(e.to_string()))?;
+            return Ok(buffer);
+        }
     };
 
     image
@@ -669,116 +2367,143 @@ fn encode_dynamic_image(image: DynamicImage, format: &Format) -> Result<Vec<u8>,
     Ok(buffer)
 }
 
-fn extract_image_from_3mf(
-    threemf_path: &PathBuf,
-    width: u32,
-    height: u32,
-    image_path: &PathBuf,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let image = load_thumbnail_from_3mf(threemf_path)?;
-    resize_dynamic_image(image, width, height).save(image_path)?;
-    Ok(())
-}
-
 fn load_thumbnail_from_3mf(
     threemf_path: &PathBuf,
+    options: &ThumbnailOptions,
 ) -> Result<DynamicImage, Box<dyn std::error::Error>> {
     let file = File::open(threemf_path)?;
     let mut zip = ZipArchive::new(file)?;
+    let mut threemf_images: Vec<EmbeddedImage> = Vec::new();
 
     for i in 0..zip.len() {
         let mut file = zip.by_index(i)?;
-        if file.name().ends_with("thumbnail_middle.png") {
+        let name = file.name().to_ascii_lowercase();
+        let is_candidate = match options.thumbnail_selection {
+            ThumbnailSelection::Main => name.ends_with("thumbnail_middle.png"),
+            _ => name.ends_with(".png") && name.contains("thumbnail"),
+        };
+
+        if is_candidate {
             let mut buffer = Vec::with_capacity(file.size() as usize);
             file.read_to_end(&mut buffer)?;
 
-            return Ok(ImageReader::new(Cursor::new(buffer))
+            let (width, height) = match ImageReader::new(Cursor::new(&buffer))
                 .with_guessed_format()?
-                .decode()?);
+                .into_dimensions()
+            {
+                Ok(dimensions) => dimensions,
+                Err(_) => continue,
+            };
+
+            threemf_images.push(EmbeddedImage {
+                width,
+                height,
+                data: buffer,
+            });
         }
     }
 
-    Err(Box::new(std::io::Error::new(
-        std::io::ErrorKind::NotFound,
-        "thumbnail_middle.png not found in 3mf file",
-    )))
-}
+    let selected_image = match select_embedded_image(&threemf_images, options) {
+        Some(x) => x,
+        None => {
+            return Err(Box::new(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No thumbnail found in 3mf file",
+            )));
+        }
+    };
 
-fn extract_image_from_3mf_to_bytes(
-    threemf_path: &PathBuf,
-    width: u32,
-    height: u32,
-    format: &Format,
-) -> Result<Vec<u8>, ThumbnailError> {
-    let image =
-        load_thumbnail_from_3mf(threemf_path).map_err(|e| ThumbnailError::Other(e.to_string()))?;
-    let resized = resize_dynamic_image(image, width, height);
-    encode_dynamic_image(resized, format)
+    Ok(ImageReader::new(Cursor::new(&selected_image.data))
+        .with_guessed_format()?
+        .decode()?)
 }
 
-struct GcodeImage {
+struct EmbeddedImage {
     width: u32,
     height: u32,
     data: Vec<u8>,
 }
 
-impl GcodeImage {
+impl EmbeddedImage {
     fn area(&self) -> u32 {
         self.width * self.height
     }
+
+    fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+}
+
+///
+/// Picks one of the thumbnails embedded in a 3mf or gcode file according to `options.thumbnail_selection`.
+/// Ties go to the image listed first in the file.
+///
+fn select_embedded_image<'a>(
+    images: &'a [EmbeddedImage],
+    options: &ThumbnailOptions,
+) -> Option<&'a EmbeddedImage> {
+    let largest = images.iter().reduce(|largest, image| {
+        if image.area() > largest.area() {
+            image
+        } else {
+            largest
+        }
+    });
+
+    match options.thumbnail_selection {
+        ThumbnailSelection::Main | ThumbnailSelection::Largest => largest,
+        ThumbnailSelection::SmallestFitting => images
+            .iter()
+            .filter(|image| image.width >= options.width && image.height >= options.height)
+            .min_by_key(|image| image.area())
+            .or(largest),
+        ThumbnailSelection::ClosestAspect => {
+            let target_aspect = options.width as f32 / options.height as f32;
+            images.iter().min_by(|a, b| {
+                let a_distance = (a.aspect_ratio() / target_aspect).ln().abs();
+                let b_distance = (b.aspect_ratio() / target_aspect).ln().abs();
+                a_distance
+                    .total_cmp(&b_distance)
+                    .then_with(|| b.area().cmp(&a.area()))
+            })
+        }
+    }
 }
 
-fn extract_image_from_gcode_file(
+fn load_thumbnail_from_gcode_file(
     gcode_path: &PathBuf,
-    width: u32,
-    height: u32,
-    image_path: &PathBuf,
-) -> Result<(), Box<dyn std::error::Error>> {
+    options: &ThumbnailOptions,
+) -> Result<DynamicImage, Box<dyn std::error::Error>> {
     let mut file = File::open(gcode_path)?;
-    extract_image_from_gcode(&mut file, width, height, image_path)
+    load_thumbnail_from_gcode_reader(&mut file, options)
 }
 
-fn extract_image_from_gcode_zip(
+fn load_thumbnail_from_gcode_zip(
     gcode_zip_path: &PathBuf,
-    width: u32,
-    height: u32,
-    image_path: &PathBuf,
-) -> Result<(), Box<dyn std::error::Error>> {
+    options: &ThumbnailOptions,
+) -> Result<DynamicImage, Box<dyn std::error::Error>> {
     let file = File::open(gcode_zip_path)?;
     let mut zip = ZipArchive::new(file)?;
 
     for i in 0..zip.len() {
         let mut file = zip.by_index(i)?;
         if file.name().ends_with(".gcode") {
-            return extract_image_from_gcode(&mut file, width, height, image_path);
+            return load_thumbnail_from_gcode_reader(&mut file, options);
         }
     }
 
     Err("No gcode file found in zip archive".into())
 }
 
-fn extract_image_from_gcode<W>(
-    reader: &mut W,
-    width: u32,
-    height: u32,
-    image_path: &PathBuf,
-) -> Result<(), Box<dyn std::error::Error>>
-where
-    W: Read,
-{
-    let image = load_thumbnail_from_gcode_reader(reader)?;
-    resize_dynamic_image(image, width, height).save(image_path)?;
-    Ok(())
-}
-
 fn load_thumbnail_from_gcode_reader<W>(
     reader: &mut W,
+    options: &ThumbnailOptions,
 ) -> Result<DynamicImage, Box<dyn std::error::Error>>
 where
     W: Read,
 {
     let buffered_reader = BufReader::new(reader);
-    let mut gcode_images: Vec<GcodeImage> = Vec::new();
+    let mut gcode_images: Vec<EmbeddedImage> = Vec::new();
     let mut in_gcode_section = false;
     let mut gcode_img_width = 0;
     let mut gcode_img_height = 0;
@@ -811,7 +2536,7 @@ where
                 }
             };
 
-            gcode_images.push(GcodeImage {
+            gcode_images.push(EmbeddedImage {
                 width: gcode_img_width,
                 height: gcode_img_height,
                 data: image,
@@ -823,72 +2548,390 @@ where
         }
     }
 
-    gcode_images.sort_by(|a, b| b.area().cmp(&a.area()));
-
-    let largest_image = match gcode_images.first() {
+    let selected_image = match select_embedded_image(&gcode_images, options) {
         Some(x) => x,
         None => return Err("No thumbnail found in gcode file".into()),
     };
 
-    Ok(ImageReader::new(Cursor::new(&largest_image.data))
+    Ok(ImageReader::new(Cursor::new(&selected_image.data))
         .with_guessed_format()?
         .decode()?)
 }
 
-fn extract_image_from_gcode_reader_to_bytes<W>(
-    reader: &mut W,
+fn replace_file_stem(path: &mut PathBuf, new_stem: &str) {
+    if let Some(ext) = path.extension() {
+        path.set_file_name(format!("{}.{}", new_stem, ext.to_string_lossy()));
+    } else {
+        path.set_file_name(new_stem);
+    }
+}
+
+fn background_rgba(format: &Format) -> [u8; 4] {
+    let alpha = if format.has_alpha() { 0 } else { 204 };
+    [51, 51, 51, alpha]
+}
+
+fn resize_dynamic_image(image: DynamicImage, options: &ThumbnailOptions) -> DynamicImage {
+    if options.auto_crop {
+        let image = image.to_rgba8();
+        // Embedded thumbnails have their own background, take it from the top left corner
+        let background = *image.get_pixel(0, 0);
+        let cropped = crop_to_content(&image, background.0);
+        return DynamicImage::ImageRgba8(fit_to_canvas(
+            &cropped,
+            options.width,
+            options.height,
+            options.crop_margin,
+            background,
+            Triangle,
+        ));
+    }
+
+    if !options.letterbox_thumbnail {
+        return image.resize_to_fill(options.width, options.height, Triangle);
+    }
+
+    DynamicImage::ImageRgba8(fit_to_canvas(
+        &image.to_rgba8(),
+        options.width,
+        options.height,
+        0.0,
+        Rgba(background_rgba(&options.format)),
+        Triangle,
+    ))
+}
+
+///
+/// Scales `image` to fit inside `width` x `height` minus `margin` percent on each side, padding the rest with `background`.
+/// Generic over the pixel type so depth and normal maps get exactly the same placement as the color image.
+///
+fn fit_to_canvas<P>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
     width: u32,
     height: u32,
-    format: &Format,
-) -> Result<Vec<u8>, ThumbnailError>
+    margin: f32,
+    background: P,
+    filter: FilterType,
+) -> ImageBuffer<P, Vec<P::Subpixel>>
 where
-    W: Read,
+    P: Pixel + 'static,
 {
-    let image = load_thumbnail_from_gcode_reader(reader)
-        .map_err(|e| ThumbnailError::Other(e.to_string()))?;
-    let resized = resize_dynamic_image(image, width, height);
-    encode_dynamic_image(resized, format)
+    let fill = (1.0 - 2.0 * margin / 100.0).clamp(0.01, 1.0);
+    let inner_width = ((width as f32 * fill) as u32).max(1);
+    let inner_height = ((height as f32 * fill) as u32).max(1);
+
+    // Keep the aspect ratio of `image`
+    let scale = (inner_width as f64 / image.width() as f64)
+        .min(inner_height as f64 / image.height() as f64);
+    let resized_width = ((image.width() as f64 * scale).round() as u32).clamp(1, width);
+    let resized_height = ((image.height() as f64 * scale).round() as u32).clamp(1, height);
+
+    let resized = imageops::resize(image, resized_width, resized_height, filter);
+    let mut canvas = ImageBuffer::from_pixel(width, height, background);
+    let x = (width - resized.width()) / 2;
+    let y = (height - resized.height()) / 2;
+    imageops::overlay(&mut canvas, &resized, x as i64, y as i64);
+
+    canvas
 }
 
-fn extract_image_from_gcode_file_to_bytes(
-    gcode_path: &PathBuf,
-    width: u32,
-    height: u32,
-    format: &Format,
-) -> Result<Vec<u8>, ThumbnailError> {
-    let mut file = File::open(gcode_path)?;
-    extract_image_from_gcode_reader_to_bytes(&mut file, width, height, format)
+/// Bounding box `(x, y, width, height)` of all pixels that differ from `background`, the whole image when there are none.
+fn content_bounds(image: &RgbaImage, background: [u8; 4]) -> (u32, u32, u32, u32) {
+    let (mut min_x, mut min_y) = (u32::MAX, u32::MAX);
+    let (mut max_x, mut max_y) = (0, 0);
+
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let is_background = pixel
+            .0
+            .iter()
+            .zip(background.iter())
+            .all(|(a, b)| a.abs_diff(*b) <= 8);
+
+        if !is_background {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+
+    if min_x > max_x || min_y > max_y {
+        return (0, 0, image.width(), image.height());
+    }
+
+    (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1)
 }
 
-fn extract_image_from_gcode_zip_to_bytes(
-    gcode_zip_path: &PathBuf,
-    width: u32,
-    height: u32,
-    format: &Format,
-) -> Result<Vec<u8>, ThumbnailError> {
-    let file = File::open(gcode_zip_path)?;
-    let mut zip = ZipArchive::new(file)?;
+/// Smallest rectangle `(x, y, width, height)` that contains both `a` and `b`.
+fn union_bounds(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> (u32, u32, u32, u32) {
+    let min_x = a.0.min(b.0);
+    let min_y = a.1.min(b.1);
+    let max_x = (a.0 + a.2).max(b.0 + b.2);
+    let max_y = (a.1 + a.3).max(b.1 + b.3);
 
-    for i in 0..zip.len() {
-        let mut file = zip.by_index(i)?;
-        if file.name().ends_with(".gcode") {
-            return extract_image_from_gcode_reader_to_bytes(&mut file, width, height, format);
+    (min_x, min_y, max_x - min_x, max_y - min_y)
+}
+
+/// Crops `image` to the bounding box of all pixels that differ from `background`.
+fn crop_to_content(image: &RgbaImage, background: [u8; 4]) -> RgbaImage {
+    let (x, y, width, height) = content_bounds(image, background);
+    imageops::crop_imm(image, x, y, width, height).to_image()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn embedded(id: u8, width: u32, height: u32) -> EmbeddedImage {
+        EmbeddedImage {
+            width,
+            height,
+            data: vec![id],
         }
     }
 
-    Err(ThumbnailError::Other(String::from(
-        "No gcode file found in zip archive",
-    )))
-}
+    fn select(
+        images: &[EmbeddedImage],
+        selection: ThumbnailSelection,
+        width: u32,
+        height: u32,
+    ) -> Option<u8> {
+        let options = ThumbnailOptions {
+            width,
+            height,
+            thumbnail_selection: selection,
+            ..Default::default()
+        };
 
-fn replace_file_stem(path: &mut PathBuf, new_stem: &str) {
-    if let Some(ext) = path.extension() {
-        path.set_file_name(format!("{}.{}", new_stem, ext.to_string_lossy()));
-    } else {
-        path.set_file_name(new_stem);
+        select_embedded_image(images, &options).map(|image| image.data[0])
+    }
+
+    #[test]
+    fn select_without_images_returns_none() {
+        assert_eq!(select(&[], ThumbnailSelection::Largest, 256, 256), None);
+        assert_eq!(
+            select(&[], ThumbnailSelection::SmallestFitting, 256, 256),
+            None
+        );
+        assert_eq!(
+            select(&[], ThumbnailSelection::ClosestAspect, 256, 256),
+            None
+        );
+    }
+
+    #[test]
+    fn select_largest_and_main_pick_the_largest_area() {
+        let images = [
+            embedded(0, 32, 32),
+            embedded(1, 400, 300),
+            embedded(2, 256, 256),
+        ];
+
+        assert_eq!(
+            select(&images, ThumbnailSelection::Largest, 64, 64),
+            Some(1)
+        );
+        assert_eq!(select(&images, ThumbnailSelection::Main, 64, 64), Some(1));
+    }
+
+    #[test]
+    fn select_largest_keeps_the_first_of_equal_areas() {
+        let images = [
+            embedded(0, 16, 16),
+            embedded(1, 200, 100),
+            embedded(2, 100, 200),
+        ];
+
+        assert_eq!(
+            select(&images, ThumbnailSelection::Largest, 64, 64),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn select_smallest_fitting_picks_the_smallest_covering_the_target() {
+        let images = [
+            embedded(0, 512, 512),
+            embedded(1, 128, 128),
+            embedded(2, 300, 300),
+        ];
+
+        assert_eq!(
+            select(&images, ThumbnailSelection::SmallestFitting, 256, 200),
+            Some(2)
+        );
+        assert_eq!(
+            select(&images, ThumbnailSelection::SmallestFitting, 128, 128),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn select_smallest_fitting_needs_both_dimensions() {
+        let images = [embedded(0, 600, 100), embedded(1, 300, 300)];
+
+        assert_eq!(
+            select(&images, ThumbnailSelection::SmallestFitting, 256, 256),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn select_smallest_fitting_falls_back_to_the_largest() {
+        let images = [
+            embedded(0, 32, 32),
+            embedded(1, 128, 96),
+            embedded(2, 64, 64),
+        ];
+
+        assert_eq!(
+            select(&images, ThumbnailSelection::SmallestFitting, 512, 512),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn select_smallest_fitting_keeps_the_first_of_equal_areas() {
+        let images = [
+            embedded(0, 300, 200),
+            embedded(1, 200, 300),
+            embedded(2, 400, 400),
+        ];
+
+        assert_eq!(
+            select(&images, ThumbnailSelection::SmallestFitting, 200, 200),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn select_closest_aspect_matches_the_target_shape() {
+        let images = [
+            embedded(0, 512, 512),
+            embedded(1, 320, 180),
+            embedded(2, 180, 320),
+        ];
+
+        assert_eq!(
+            select(&images, ThumbnailSelection::ClosestAspect, 1920, 1080),
+            Some(1)
+        );
+        assert_eq!(
+            select(&images, ThumbnailSelection::ClosestAspect, 1080, 1920),
+            Some(2)
+        );
+        assert_eq!(
+            select(&images, ThumbnailSelection::ClosestAspect, 256, 256),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn select_closest_aspect_prefers_the_larger_of_equal_shapes() {
+        let images = [
+            embedded(0, 160, 90),
+            embedded(1, 640, 360),
+            embedded(2, 320, 180),
+        ];
+
+        assert_eq!(
+            select(&images, ThumbnailSelection::ClosestAspect, 1920, 1080),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn select_closest_aspect_treats_wider_and_taller_alike() {
+        // 2:1 and 1:2 are equally far from a square target, so the larger one wins
+        let images = [embedded(0, 200, 100), embedded(1, 150, 300)];
+
+        assert_eq!(
+            select(&images, ThumbnailSelection::ClosestAspect, 100, 100),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn content_bounds_finds_the_covered_pixels() {
+        let mut image = RgbaImage::from_pixel(20, 10, Rgba([221, 221, 221, 255]));
+        image.put_pixel(3, 2, Rgba([40, 40, 40, 255]));
+        image.put_pixel(12, 7, Rgba([40, 40, 40, 255]));
+
+        assert_eq!(content_bounds(&image, [221, 221, 221, 255]), (3, 2, 10, 6));
+    }
+
+    #[test]
+    fn content_bounds_of_a_single_pixel() {
+        let mut image = RgbaImage::new(8, 8);
+        image.put_pixel(7, 0, Rgba([255, 0, 0, 255]));
+
+        assert_eq!(content_bounds(&image, [0, 0, 0, 0]), (7, 0, 1, 1));
+    }
+
+    #[test]
+    fn content_bounds_ignores_pixels_close_to_the_background() {
+        let mut image = RgbaImage::from_pixel(8, 8, Rgba([100, 100, 100, 255]));
+        image.put_pixel(1, 1, Rgba([108, 92, 100, 255]));
+        image.put_pixel(5, 6, Rgba([109, 100, 100, 255]));
+
+        assert_eq!(content_bounds(&image, [100, 100, 100, 255]), (5, 6, 1, 1));
+    }
+
+    #[test]
+    fn content_bounds_of_a_transparent_image_is_the_whole_image() {
+        let image = RgbaImage::new(16, 9);
+
+        assert_eq!(content_bounds(&image, [0, 0, 0, 0]), (0, 0, 16, 9));
+        assert_eq!(crop_to_content(&image, [0, 0, 0, 0]).dimensions(), (16, 9));
+    }
+
+    #[test]
+    fn content_bounds_of_an_empty_image() {
+        let image = RgbaImage::new(0, 0);
+
+        assert_eq!(content_bounds(&image, [0, 0, 0, 0]), (0, 0, 0, 0));
+        assert_eq!(crop_to_content(&image, [0, 0, 0, 0]).dimensions(), (0, 0));
+    }
+
+    #[test]
+    fn part_colors_cycle_through_the_palette() {
+        let colors: Vec<Srgba> = (0..PART_PALETTE.len()).map(part_color).collect();
+
+        for (i, color) in colors.iter().enumerate() {
+            assert!(!colors[..i].contains(color));
+            assert_eq!(part_color(i + PART_PALETTE.len()), *color);
+        }
+        assert_eq!(part_color(0), rgb_to_srgba(0x4E79A7));
     }
-}
 
-fn resize_dynamic_image(image: DynamicImage, width: u32, height: u32) -> DynamicImage {
-    image.resize_to_fill(width, height, Triangle)
+    #[test]
+    fn file_name_colors_are_deterministic() {
+        let names = ["benchy.stl", "benchy.3mf", "bracket.stl", ""];
+
+        for name in names {
+            assert_eq!(file_name_color(name), file_name_color(name));
+            assert_eq!(file_name_color(name).a, 255);
+        }
+        assert_ne!(
+            file_name_color("benchy.stl"),
+            file_name_color("bracket.stl")
+        );
+    }
+
+    #[test]
+    fn antialias_factor_is_a_power_of_two_that_fits_the_texture() {
+        assert_eq!(antialias_factor(1, 512, 16384), 1);
+        assert_eq!(antialias_factor(3, 512, 16384), 2);
+        assert_eq!(antialias_factor(6, 512, 16384), 4);
+        assert_eq!(antialias_factor(8, 512, 16384), 8);
+        assert_eq!(antialias_factor(8, 4096, 16384), 4);
+        assert_eq!(antialias_factor(8, 4096, 4096), 1);
+        assert_eq!(antialias_factor(8, 512, 0), 1);
+    }
+
+    #[test]
+    fn union_bounds_covers_both_rectangles() {
+        assert_eq!(union_bounds((2, 3, 4, 5), (1, 6, 2, 4)), (1, 3, 5, 7));
+        assert_eq!(union_bounds((2, 3, 4, 5), (3, 4, 1, 1)), (2, 3, 4, 5));
+    }
 }
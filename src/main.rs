@@ -1,7 +1,12 @@
 use clap::Parser;
 use std::path::PathBuf;
 
-use mesh_thumbnail::{Format, ThumbnailOptions, generate_thumbnail_for_file};
+use mesh_thumbnail::{
+    AnimationFormat, AutoOrient, BedShape, ColorMode, DimensionUnits, Format, GifPalette,
+    LightingRig, MaterialPreset, ModelUnits, PngCompression, Projection, RenderMode, SectionAxis,
+    ThumbnailOptions, ThumbnailSelection, UpAxis, ViewPreset, generate_overview_for_paths,
+    generate_thumbnail_for_file,
+};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -67,6 +72,256 @@ struct Args {
     #[arg(long, default_value_t = 1.0)]
     /// Scale factor for the camera
     inverse_zoom: f32,
+
+    /// Which embedded 3mf/gcode thumbnail to use, main is thumbnail_middle.png in 3mf files and the largest in gcode files
+    #[arg(long, default_value_t = ThumbnailSelection::Main, value_enum)]
+    thumbnail_selection: ThumbnailSelection,
+
+    /// Letterbox embedded thumbnails instead of cropping them to fill
+    #[arg(long, default_value_t = false)]
+    letterbox_thumbnail: bool,
+
+    /// Camera projection
+    #[arg(long, default_value_t = Projection::Perspective, value_enum)]
+    projection: Projection,
+
+    /// Named camera view, overrides rotatex and rotatey
+    #[arg(long, value_enum)]
+    view: Option<ViewPreset>,
+
+    /// Fit the camera tightly around the model
+    #[arg(long, default_value_t = false)]
+    auto_frame: bool,
+
+    /// Margin around the model in percent of the image size when auto framing
+    #[arg(long, default_value_t = 5.0)]
+    frame_margin: f32,
+
+    /// Crop the output image to the model and scale it back to the requested size
+    #[arg(long, default_value_t = false)]
+    auto_crop: bool,
+
+    /// Margin around the cropped content in percent of the image size
+    #[arg(long, default_value_t = 5.0)]
+    crop_margin: f32,
+
+    /// Supersampling factor for anti-aliasing, rounded down to 1, 2, 4 or 8 (1 disables it)
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=8))]
+    antialias: u32,
+
+    /// Draw silhouette and crease outlines
+    #[arg(long, default_value_t = false)]
+    outline: bool,
+
+    /// Outline color in hex format
+    #[arg(long, default_value = "000000")]
+    outline_color: String,
+
+    /// Outline width in pixels
+    #[arg(long, default_value_t = 1.0)]
+    outline_width: f32,
+
+    /// Minimum angle in degrees between faces for a crease outline
+    #[arg(long, default_value_t = 40.0)]
+    crease_angle: f32,
+
+    /// How the model surface is drawn
+    #[arg(long, default_value_t = RenderMode::Shaded, value_enum)]
+    render_mode: RenderMode,
+
+    /// Lights used to shade the model
+    #[arg(long, default_value_t = LightingRig::Headlight, value_enum)]
+    lighting: LightingRig,
+
+    /// Surface material preset
+    #[arg(long, default_value_t = MaterialPreset::Matte, value_enum)]
+    material: MaterialPreset,
+
+    /// Ambient light strength, overrides the material preset
+    #[arg(long)]
+    ambient: Option<f32>,
+
+    /// Specular highlight strength, overrides the material preset
+    #[arg(long)]
+    specular: Option<f32>,
+
+    /// Darken cavities with screen space ambient occlusion
+    #[arg(long, default_value_t = false)]
+    ambient_occlusion: bool,
+
+    /// Strength of the ambient occlusion
+    #[arg(long, default_value_t = 1.0)]
+    ao_strength: f32,
+
+    /// Draw a soft shadow on the ground below the model
+    #[arg(long, default_value_t = false)]
+    ground_shadow: bool,
+
+    /// Opacity of the ground shadow
+    #[arg(long, default_value_t = 0.5)]
+    shadow_strength: f32,
+
+    /// Shade with smooth vertex normals instead of flat faces, OBJ files use their own normals when every vertex has one
+    #[arg(long, default_value_t = false)]
+    smooth_normals: bool,
+
+    /// Angle in degrees above which edges stay hard when smoothing normals, not applied to normals from the file
+    #[arg(long, default_value_t = 30.0)]
+    smooth_angle: f32,
+
+    /// Draw a build plate with a grid below the model
+    #[arg(long, default_value_t = false)]
+    build_plate: bool,
+
+    /// Distance between grid lines in millimeters
+    #[arg(long, default_value_t = 10.0)]
+    grid_spacing: f32,
+
+    /// Shape of the build plate
+    #[arg(long, default_value_t = BedShape::Rectangle, value_enum)]
+    bed_shape: BedShape,
+
+    /// Width of the build plate in millimeters, defaults to the printer profile in the file or the model size
+    #[arg(long)]
+    bed_width: Option<f32>,
+
+    /// Depth of the build plate in millimeters, defaults to the bed width
+    #[arg(long)]
+    bed_depth: Option<f32>,
+
+    /// Write the model dimensions into the bottom left corner
+    #[arg(long, default_value_t = false)]
+    dimensions: bool,
+
+    /// Draw dimension lines along the bounding box edges
+    #[arg(long, default_value_t = false)]
+    dimension_lines: bool,
+
+    /// Units used for the dimensions
+    #[arg(long, default_value_t = DimensionUnits::Mm, value_enum)]
+    dimension_units: DimensionUnits,
+
+    /// Combine the rotating views into one animation, uses 36 frames unless images-per-file is set
+    #[arg(long, value_enum)]
+    animation: Option<AnimationFormat>,
+
+    /// Frames per second of the animation
+    #[arg(long, default_value_t = 15.0)]
+    frame_rate: f32,
+
+    /// Number of times the animation plays, 0 loops forever
+    #[arg(long, default_value_t = 0)]
+    loop_count: u32,
+
+    /// Palette used for GIF animations
+    #[arg(long, default_value_t = GifPalette::Global, value_enum)]
+    gif_palette: GifPalette,
+
+    /// Render these views side by side into one image, overrides view, rotatex and rotatey
+    #[arg(long, value_enum, value_delimiter = ',')]
+    contact_sheet: Vec<ViewPreset>,
+
+    /// Number of columns of the contact sheet, 0 picks a square grid
+    #[arg(long, default_value_t = 0)]
+    sheet_columns: u32,
+
+    /// Write the view name into each contact sheet cell
+    #[arg(long, default_value_t = false)]
+    sheet_labels: bool,
+
+    /// Render all inputs into one overview image at this path, inputs may also be folders and zip archives of models
+    #[arg(long)]
+    overview: Option<String>,
+
+    /// Size in pixels of each model tile in the overview
+    #[arg(long, default_value_t = 256)]
+    tile_size: u32,
+
+    /// Number of columns of the overview, 0 picks a square grid
+    #[arg(long, default_value_t = 0)]
+    overview_columns: u32,
+
+    /// Push the parts of multi-part files apart by this factor of their distance to the assembly center (0 disables it)
+    #[arg(long, default_value_t = 0.0)]
+    explode: f32,
+
+    /// Cut the model with a plane normal to this printer axis, removing the side the axis points to
+    #[arg(long, value_enum)]
+    section: Option<SectionAxis>,
+
+    /// Normal of the section plane in printer coordinates, overrides section
+    #[arg(long, num_args = 3, value_names = ["X", "Y", "Z"])]
+    #[clap(allow_hyphen_values = true)]
+    section_normal: Vec<f32>,
+
+    /// Distance in millimeters of the section plane from the model center along its normal
+    #[arg(long, default_value_t = 0.0)]
+    #[clap(allow_hyphen_values = true)]
+    section_offset: f32,
+
+    /// Color of the cut faces in hex format
+    #[arg(long, default_value = "E0452C")]
+    section_color: String,
+
+    /// Color faces by their angle to the build direction, marking overhangs and faces on the bed
+    #[arg(long, default_value_t = false)]
+    overhangs: bool,
+
+    /// Angle in degrees from vertical beyond which a downward facing surface is an overhang
+    #[arg(long, default_value_t = 45.0)]
+    overhang_angle: f32,
+
+    /// Overhang color in hex format
+    #[arg(long, default_value = "E02020")]
+    overhang_color: String,
+
+    /// Color of faces resting on the bed in hex format
+    #[arg(long, default_value = "2E7DD7")]
+    bed_contact_color: String,
+
+    /// How parts without colors from the file are colored
+    #[arg(long, default_value_t = ColorMode::Single, value_enum)]
+    color_mode: ColorMode,
+
+    /// Lay the model on a stable face of its convex hull before rendering
+    #[arg(long, value_enum)]
+    auto_orient: Option<AutoOrient>,
+
+    /// Axis pointing up in STL, 3MF and OBJ files, auto uses Y for OBJ and Z otherwise
+    #[arg(long, default_value_t = UpAxis::Auto, value_enum)]
+    up_axis: UpAxis,
+
+    /// Unit of the model coordinates, auto uses the unit declared by 3MF files and millimeters otherwise
+    #[arg(long, default_value_t = ModelUnits::Auto, value_enum)]
+    units: ModelUnits,
+
+    /// Quality of lossy WebP and AVIF output, from 1 to 100
+    #[arg(long, default_value_t = 80, value_parser = clap::value_parser!(u8).range(1..=100))]
+    quality: u8,
+
+    /// Encode WebP output losslessly
+    #[arg(long, default_value_t = false)]
+    lossless: bool,
+
+    /// Quality of JPEG output, from 1 to 100
+    #[arg(long, default_value_t = 90, value_parser = clap::value_parser!(u8).range(1..=100))]
+    jpeg_quality: u8,
+
+    /// Compression effort of PNG output
+    #[arg(long, default_value_t = PngCompression::Default, value_enum)]
+    png_compression: PngCompression,
+
+    /// Quantize PNG output to a palette of at most this many colors
+    #[arg(long, value_name = "COLORS", value_parser = clap::value_parser!(u16).range(2..=256))]
+    png_palette: Option<u16>,
+
+    /// Also write a 16 bit depth PNG of every view with a -depth suffix, not for animations and contact sheets
+    #[arg(long, default_value_t = false)]
+    depth_map: bool,
+
+    /// Also write a view space normal map PNG of every view with a -normal suffix, not for animations and contact sheets
+    #[arg(long, default_value_t = false)]
+    normal_map: bool,
 }
 
 fn main() {
@@ -100,8 +355,80 @@ fn main() {
         prefer_gcode_thumbnail: args.prefer_gcode_thumbnail,
         images_per_file: args.images_per_file,
         inverse_zoom: args.inverse_zoom,
+        thumbnail_selection: args.thumbnail_selection,
+        letterbox_thumbnail: args.letterbox_thumbnail,
+        projection: args.projection,
+        view: args.view,
+        auto_frame: args.auto_frame,
+        frame_margin: args.frame_margin,
+        auto_crop: args.auto_crop,
+        crop_margin: args.crop_margin,
+        antialias: args.antialias,
+        outline: args.outline,
+        outline_color: args.outline_color,
+        outline_width: args.outline_width,
+        crease_angle: args.crease_angle,
+        render_mode: args.render_mode,
+        lighting: args.lighting,
+        material: args.material,
+        ambient: args.ambient,
+        specular: args.specular,
+        ambient_occlusion: args.ambient_occlusion,
+        ao_strength: args.ao_strength,
+        ground_shadow: args.ground_shadow,
+        shadow_strength: args.shadow_strength,
+        smooth_normals: args.smooth_normals,
+        smooth_angle: args.smooth_angle,
+        build_plate: args.build_plate,
+        grid_spacing: args.grid_spacing,
+        bed_shape: args.bed_shape,
+        bed_width: args.bed_width,
+        bed_depth: args.bed_depth,
+        dimensions: args.dimensions,
+        dimension_lines: args.dimension_lines,
+        dimension_units: args.dimension_units,
+        animation: args.animation,
+        frame_rate: args.frame_rate,
+        loop_count: args.loop_count,
+        gif_palette: args.gif_palette,
+        contact_sheet: args.contact_sheet,
+        sheet_columns: args.sheet_columns,
+        sheet_labels: args.sheet_labels,
+        tile_size: args.tile_size,
+        overview_columns: args.overview_columns,
+        explode: args.explode,
+        section: args.section,
+        section_normal: match args.section_normal[..] {
+            [x, y, z] => Some([x, y, z]),
+            _ => None,
+        },
+        section_offset: args.section_offset,
+        section_color: args.section_color,
+        overhangs: args.overhangs,
+        overhang_angle: args.overhang_angle,
+        overhang_color: args.overhang_color,
+        bed_contact_color: args.bed_contact_color,
+        color_mode: args.color_mode,
+        auto_orient: args.auto_orient,
+        up_axis: args.up_axis,
+        units: args.units,
+        quality: args.quality,
+        lossless: args.lossless,
+        jpeg_quality: args.jpeg_quality,
+        png_compression: args.png_compression,
+        png_palette: args.png_palette,
+        depth_map: args.depth_map,
+        normal_map: args.normal_map,
     };
 
+    if let Some(overview) = &args.overview {
+        let paths: Vec<PathBuf> = args.files.iter().map(PathBuf::from).collect();
+        if let Err(e) = generate_overview_for_paths(&paths, &PathBuf::from(overview), &options) {
+            eprintln!("Error while creating overview {}: {:?}.", overview, e);
+        }
+        return;
+    }
+
     let outdir = PathBuf::from(&args.outdir);
 
     for file in args.files {
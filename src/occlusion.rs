@@ -0,0 +1,254 @@
+use three_d::*;
+
+use crate::screen_space::GBuffer;
+
+const SAMPLE_COUNT: usize = 16;
+
+// Angle between consecutive samples of the spiral, spreads samples evenly over the disc
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+///
+/// Darkens creases and cavities with screen space ambient occlusion.
+/// Every pixel samples its neighbours within `radius` pixels and is occluded by those lying above its surface.
+///
+pub fn apply_ambient_occlusion(
+    pixels: &mut [[u8; 4]],
+    gbuffer: &GBuffer,
+    radius: f32,
+    strength: f32,
+) {
+    let mut factors = vec![1.0f32; gbuffer.width * gbuffer.height];
+
+    for y in 0..gbuffer.height {
+        for x in 0..gbuffer.width {
+            let i = y * gbuffer.width + x;
+            let (position, normal) = match (gbuffer.position(i), gbuffer.normal(i)) {
+                (Some(position), Some(normal)) => (position, normal),
+                _ => continue,
+            };
+
+            let pixel_size = [(1, 0), (0, 1), (-1, 0), (0, -1)]
+                .iter()
+                .filter_map(|(dx, dy)| gbuffer.offset_index(x, y, *dx, *dy))
+                .filter_map(|j| gbuffer.position(j))
+                .map(|neighbour| (neighbour - position).magnitude())
+                .fold(f32::INFINITY, f32::min);
+
+            if !pixel_size.is_finite() {
+                continue;
+            }
+
+            let world_radius = pixel_size * radius;
+            // Rotate the pattern per pixel to trade banding for noise, which the blur below removes
+            let rotation = ((x * 7 + y * 13) % 16) as f32 / 16.0 * std::f32::consts::TAU;
+            let mut occlusion = 0.0;
+
+            for sample in 0..SAMPLE_COUNT {
+                let distance = radius * (sample as f32 + 0.5) / SAMPLE_COUNT as f32;
+                let angle = rotation + sample as f32 * GOLDEN_ANGLE;
+                let dx = (angle.cos() * distance).round() as isize;
+                let dy = (angle.sin() * distance).round() as isize;
+
+                let other = match gbuffer
+                    .offset_index(x, y, dx, dy)
+                    .and_then(|j| gbuffer.position(j))
+                {
+                    Some(other) => other,
+                    None => continue,
+                };
+
+                let offset = other - position;
+                let length = offset.magnitude();
+
+                if length <= f32::EPSILON {
+                    continue;
+                }
+
+                let elevation = (normal.dot(offset / length) - 0.1).max(0.0);
+                let falloff = (1.0 - length / (2.0 * world_radius)).max(0.0);
+                occlusion += elevation * falloff;
+            }
+
+            factors[i] = (1.0 - strength * occlusion / SAMPLE_COUNT as f32).clamp(0.0, 1.0);
+        }
+    }
+
+    for y in 0..gbuffer.height {
+        for x in 0..gbuffer.width {
+            let i = y * gbuffer.width + x;
+            if !gbuffer.is_covered(i) {
+                continue;
+            }
+
+            let mut sum = 0.0;
+            let mut count = 0.0;
+
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if let Some(j) = gbuffer.offset_index(x, y, dx, dy) {
+                        if gbuffer.is_covered(j) {
+                            sum += factors[j];
+                            count += 1.0;
+                        }
+                    }
+                }
+            }
+
+            let factor = sum / count;
+            let pixel = &mut pixels[i];
+            for channel in pixel.iter_mut().take(3) {
+                *channel = (*channel as f32 * factor).round() as u8;
+            }
+        }
+    }
+}
+
+///
+/// Top down coverage of the model, blurred so it can be used as a soft shadow on the ground plane.
+///
+pub struct Footprint {
+    width: usize,
+    height: usize,
+    density: Vec<f32>,
+    view_projection: Mat4,
+}
+
+impl Footprint {
+    /// Builds the footprint from the depth buffer of a top down render made with `camera`.
+    pub fn new(
+        depth: &[f32],
+        width: u32,
+        height: u32,
+        camera: &Camera,
+        blur_radius: usize,
+    ) -> Self {
+        let width = width as usize;
+        let height = height as usize;
+        let mut density: Vec<f32> = depth
+            .iter()
+            .map(|d| if *d < 1.0 { 1.0 } else { 0.0 })
+            .collect();
+
+        // Two box blur passes per axis approximate a gaussian
+        for _ in 0..2 {
+            density = box_blur(&density, width, height, blur_radius, true);
+            density = box_blur(&density, width, height, blur_radius, false);
+        }
+
+        Self {
+            width,
+            height,
+            density,
+            view_projection: camera.projection() * camera.view(),
+        }
+    }
+
+    fn sample(&self, point: Vec3) -> f32 {
+        let clip = self.view_projection * point.extend(1.0);
+        let x = ((clip.x / clip.w + 1.0) / 2.0 * self.width as f32).floor();
+        // Rows of the depth buffer run from the top of the image
+        let y = ((1.0 - clip.y / clip.w) / 2.0 * self.height as f32).floor();
+
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return 0.0;
+        }
+
+        self.density[y as usize * self.width + x as usize]
+    }
+}
+
+///
+/// Darkens the background where it shows the ground plane at `ground_height` below the model.
+/// Surfaces at or below the ground, like the build plate, are darkened as well.
+///
+pub fn apply_ground_shadow(
+    pixels: &mut [[u8; 4]],
+    gbuffer: &GBuffer,
+    footprint: &Footprint,
+    ground_height: f32,
+    strength: f32,
+) {
+    for y in 0..gbuffer.height {
+        for x in 0..gbuffer.width {
+            let i = y * gbuffer.width + x;
+            if let Some(position) = gbuffer.position(i) {
+                if position.y > ground_height {
+                    continue;
+                }
+
+                let shadow = (footprint.sample(position) * strength).clamp(0.0, 1.0);
+                for channel in pixels[i].iter_mut().take(3) {
+                    *channel = (*channel as f32 * (1.0 - shadow)).round() as u8;
+                }
+                continue;
+            }
+
+            let near = gbuffer.unproject(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+            let far = gbuffer.unproject(x as f32 + 0.5, y as f32 + 0.5, 1.0);
+            let direction = far - near;
+
+            // Only the top side of the ground receives the shadow
+            if near.y <= ground_height || direction.y.abs() <= f32::EPSILON {
+                continue;
+            }
+
+            let t = (ground_height - near.y) / direction.y;
+            if !(0.0..=1.0).contains(&t) {
+                continue;
+            }
+
+            let shadow = (footprint.sample(near + direction * t) * strength).clamp(0.0, 1.0);
+            if shadow <= 0.0 {
+                continue;
+            }
+
+            let pixel = &mut pixels[i];
+            for channel in pixel.iter_mut().take(3) {
+                *channel = (*channel as f32 * (1.0 - shadow)).round() as u8;
+            }
+            pixel[3] = (pixel[3] as f32 + (255.0 - pixel[3] as f32) * shadow).round() as u8;
+        }
+    }
+}
+
+fn box_blur(
+    values: &[f32],
+    width: usize,
+    height: usize,
+    radius: usize,
+    horizontal: bool,
+) -> Vec<f32> {
+    let mut result = vec![0.0; values.len()];
+    let (lines, length) = if horizontal {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    let index = |line: usize, position: usize| {
+        if horizontal {
+            line * width + position
+        } else {
+            position * width + line
+        }
+    };
+
+    // Sliding window sum, samples outside the image count as empty so the shadow fades out at the borders
+    for line in 0..lines {
+        let mut sum: f32 = (0..=radius.min(length - 1))
+            .map(|p| values[index(line, p)])
+            .sum();
+
+        for position in 0..length {
+            result[index(line, position)] = sum / (2 * radius + 1) as f32;
+
+            if position + radius + 1 < length {
+                sum += values[index(line, position + radius + 1)];
+            }
+            if position >= radius {
+                sum -= values[index(line, position - radius)];
+            }
+        }
+    }
+
+    result
+}
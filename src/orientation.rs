@@ -0,0 +1,584 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::ValueEnum;
+use three_d::*;
+
+use crate::parse_mesh::MeshWithTransform;
+
+/// How [resting_rotation] picks the face of the convex hull a model is laid on.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum AutoOrient {
+    /// The stable face with the largest area.
+    LargestFace,
+    /// The stable face that brings the center of mass closest to the bed.
+    LowestCenter,
+}
+
+impl ToString for AutoOrient {
+    fn to_string(&self) -> String {
+        match self {
+            AutoOrient::LargestFace => String::from("largest-face"),
+            AutoOrient::LowestCenter => String::from("lowest-center"),
+        }
+    }
+}
+
+// Hull faces whose normals differ by less than about one degree count as one flat face
+const COPLANAR_COSINE: f32 = 0.9998;
+
+///
+/// Rotation that lays the meshes on a stable face of their convex hull, with the face pointing along `down`.
+/// A face is stable when the center of mass lies above it, so the model would not tip over.
+/// Returns the identity when the meshes are flat or empty and have no hull.
+///
+pub fn resting_rotation(meshes: &[MeshWithTransform], down: Vec3, strategy: AutoOrient) -> Mat4 {
+    let mut points = Vec::new();
+    let mut triangles = Vec::new();
+
+    for mesh_with_transform in meshes {
+        let first = points.len();
+        let transform = mesh_with_transform.transform;
+        points.extend(
+            mesh_with_transform
+                .mesh
+                .positions
+                .to_f32()
+                .into_iter()
+                .map(|position| (transform * position.extend(1.0)).truncate()),
+        );
+
+        let count = points.len() - first;
+        let indices = mesh_with_transform
+            .mesh
+            .indices
+            .to_u32()
+            .unwrap_or_else(|| (0..count as u32).collect());
+        triangles.extend(indices.chunks_exact(3).map(|triangle| {
+            [
+                first + triangle[0] as usize,
+                first + triangle[1] as usize,
+                first + triangle[2] as usize,
+            ]
+        }));
+    }
+
+    let hull = convex_hull(&points);
+    if hull.is_empty() {
+        return Mat4::identity();
+    }
+
+    let center = center_of_mass(&points, &triangles);
+    let planes = hull_planes(&points, &hull);
+    let stable: Vec<&HullPlane> = planes
+        .iter()
+        .filter(|plane| plane.supports(center))
+        .collect();
+    let candidates: Vec<&HullPlane> = if stable.is_empty() {
+        planes.iter().collect()
+    } else {
+        stable
+    };
+
+    let chosen = match strategy {
+        AutoOrient::LargestFace => candidates
+            .into_iter()
+            .max_by(|a, b| a.area.total_cmp(&b.area)),
+        AutoOrient::LowestCenter => candidates.into_iter().min_by(|a, b| {
+            a.height_of(center)
+                .total_cmp(&b.height_of(center))
+                .then(b.area.total_cmp(&a.area))
+        }),
+    };
+
+    match chosen {
+        Some(plane) => Mat4::from(Quat::from_arc(plane.normal, down.normalize(), None)),
+        None => Mat4::identity(),
+    }
+}
+
+/// Volume centroid of the triangles, or the average of the points for open or flat meshes.
+fn center_of_mass(points: &[Vec3], triangles: &[[usize; 3]]) -> Vec3 {
+    let mut volume = 0.0;
+    let mut weighted = vec3(0.0, 0.0, 0.0);
+
+    for [a, b, c] in triangles.iter().map(|t| t.map(|index| points[index])) {
+        // Signed volume of the tetrahedron spanned with the origin
+        let tetrahedron = a.dot(b.cross(c)) / 6.0;
+        volume += tetrahedron;
+        weighted += (a + b + c) * (tetrahedron / 4.0);
+    }
+
+    let mean = points
+        .iter()
+        .fold(vec3(0.0, 0.0, 0.0), |sum, point| sum + *point)
+        / points.len().max(1) as f32;
+    let (min, max) = bounds(points);
+    let scale = (max - min).magnitude();
+
+    if volume.abs() > scale * scale * scale * 1e-9 {
+        weighted / volume
+    } else {
+        mean
+    }
+}
+
+fn bounds(points: &[Vec3]) -> (Vec3, Vec3) {
+    points.iter().fold(
+        (
+            vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        ),
+        |(min, max), point| {
+            (
+                vec3(min.x.min(point.x), min.y.min(point.y), min.z.min(point.z)),
+                vec3(max.x.max(point.x), max.y.max(point.y), max.z.max(point.z)),
+            )
+        },
+    )
+}
+
+/// A flat face of the hull, made of all hull triangles sharing its normal.
+struct HullPlane {
+    normal: Vec3,
+    offset: f32,
+    area: f32,
+    triangles: Vec<[Vec3; 3]>,
+}
+
+impl HullPlane {
+    /// Distance of `point` from the plane, positive on the inner side of the hull.
+    fn height_of(&self, point: Vec3) -> f32 {
+        self.offset - self.normal.dot(point)
+    }
+
+    /// Whether `point` projected onto the plane falls inside the face.
+    fn supports(&self, point: Vec3) -> bool {
+        let projected = point + self.normal * self.height_of(point);
+
+        self.triangles.iter().any(|[a, b, c]| {
+            let tolerance = -1e-6 * (*b - *a).magnitude2().max((*c - *a).magnitude2());
+            [(*a, *b), (*b, *c), (*c, *a)].iter().all(|(start, end)| {
+                (*end - *start).cross(projected - *start).dot(self.normal) >= tolerance
+            })
+        })
+    }
+}
+
+fn hull_planes(points: &[Vec3], hull: &[[usize; 3]]) -> Vec<HullPlane> {
+    let interior = hull.iter().fold(vec3(0.0, 0.0, 0.0), |sum, triangle| {
+        sum + points[triangle[0]]
+    }) / hull.len() as f32;
+
+    // Slivers can end up with a normal pointing inwards from rounding, they are left out
+    let mut triangles: Vec<([Vec3; 3], Vec3, f32)> = hull
+        .iter()
+        .filter_map(|triangle| {
+            let [a, b, c] = triangle.map(|index| points[index]);
+            let cross = (b - a).cross(c - a);
+            let area = cross.magnitude() / 2.0;
+            (area > 0.0 && cross.dot(interior - a) < 0.0)
+                .then(|| ([a, b, c], cross.normalize(), area))
+        })
+        .collect();
+    triangles.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let mut planes: Vec<HullPlane> = Vec::new();
+
+    for (triangle, normal, area) in triangles {
+        match planes
+            .iter_mut()
+            .find(|plane| plane.normal.dot(normal) > COPLANAR_COSINE)
+        {
+            Some(plane) => {
+                plane.area += area;
+                plane.triangles.push(triangle);
+            }
+            None => planes.push(HullPlane {
+                normal,
+                offset: normal.dot(triangle[0]),
+                area,
+                triangles: vec![triangle],
+            }),
+        }
+    }
+
+    planes
+}
+
+struct HullFace {
+    vertices: [usize; 3],
+    normal: Vec3,
+    offset: f32,
+    outside: Vec<usize>,
+    alive: bool,
+}
+
+impl HullFace {
+    fn new(points: &[Vec3], vertices: [usize; 3]) -> Self {
+        let [a, b, c] = vertices.map(|index| points[index]);
+        let cross = (b - a).cross(c - a);
+        let normal = if cross.magnitude2() > 0.0 {
+            cross.normalize()
+        } else {
+            vec3(0.0, 0.0, 0.0)
+        };
+
+        HullFace {
+            vertices,
+            normal,
+            offset: normal.dot(a),
+            outside: Vec::new(),
+            alive: true,
+        }
+    }
+
+    fn distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) - self.offset
+    }
+
+    fn edges(&self) -> [(usize, usize); 3] {
+        let [a, b, c] = self.vertices;
+        [(a, b), (b, c), (c, a)]
+    }
+}
+
+///
+/// Quickhull, returns the triangles of the convex hull of `points` wound counter clockwise seen from outside.
+/// Returns no triangles when the points do not span a volume.
+///
+fn convex_hull(points: &[Vec3]) -> Vec<[usize; 3]> {
+    if points.len() < 4 {
+        return Vec::new();
+    }
+
+    let (min, max) = bounds(points);
+    let scale = (max - min).magnitude();
+    if scale <= 0.0 || !scale.is_finite() {
+        return Vec::new();
+    }
+    let epsilon = scale * 1e-5;
+
+    // Initial tetrahedron from the extremes along the widest axis and the points furthest from them
+    let extent = max - min;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+    let farthest = |distance: &dyn Fn(Vec3) -> f32| {
+        (0..points.len())
+            .max_by(|a, b| distance(points[*a]).total_cmp(&distance(points[*b])))
+            .unwrap()
+    };
+    let p0 = farthest(&|point| -point[axis]);
+    let p1 = farthest(&|point| point[axis]);
+    let line = points[p1] - points[p0];
+    let p2 = farthest(&|point| line.cross(point - points[p0]).magnitude2());
+    let plane_normal = line.cross(points[p2] - points[p0]);
+
+    if plane_normal.magnitude() < epsilon * line.magnitude() {
+        return Vec::new();
+    }
+
+    let plane_normal = plane_normal.normalize();
+    let p3 = farthest(&|point| plane_normal.dot(point - points[p0]).abs());
+
+    if plane_normal.dot(points[p3] - points[p0]).abs() < epsilon {
+        return Vec::new();
+    }
+
+    let inside = (points[p0] + points[p1] + points[p2] + points[p3]) / 4.0;
+    let mut faces: Vec<HullFace> = [[p0, p1, p2], [p0, p3, p1], [p1, p3, p2], [p2, p3, p0]]
+        .into_iter()
+        .map(|[a, b, c]| {
+            let face = HullFace::new(points, [a, b, c]);
+            if face.distance(inside) > 0.0 {
+                HullFace::new(points, [a, c, b])
+            } else {
+                face
+            }
+        })
+        .collect();
+
+    // Directed edge to the face it belongs to, the opposite direction belongs to the neighbor
+    let mut edges: HashMap<(usize, usize), usize> = HashMap::new();
+    for (index, face) in faces.iter().enumerate() {
+        for edge in face.edges() {
+            edges.insert(edge, index);
+        }
+    }
+
+    for (index, point) in points.iter().enumerate() {
+        if let Some(face) = faces
+            .iter_mut()
+            .find(|face| face.distance(*point) > epsilon)
+        {
+            face.outside.push(index);
+        }
+    }
+
+    let mut stack: Vec<usize> = (0..faces.len()).collect();
+
+    while let Some(current) = stack.pop() {
+        if !faces[current].alive || faces[current].outside.is_empty() {
+            continue;
+        }
+
+        let apex = *faces[current]
+            .outside
+            .iter()
+            .max_by(|a, b| {
+                faces[current]
+                    .distance(points[**a])
+                    .total_cmp(&faces[current].distance(points[**b]))
+            })
+            .unwrap();
+
+        // All faces the apex can see form a connected region around the current face
+        let mut visible = vec![current];
+        let mut visible_set: HashSet<usize> = HashSet::from([current]);
+        let mut queue = vec![current];
+
+        while let Some(face) = queue.pop() {
+            for (a, b) in faces[face].edges() {
+                if let Some(&neighbor) = edges.get(&(b, a)) {
+                    if !visible_set.contains(&neighbor)
+                        && faces[neighbor].distance(points[apex]) > epsilon
+                    {
+                        visible_set.insert(neighbor);
+                        visible.push(neighbor);
+                        queue.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        let mut horizon = Vec::new();
+        let mut orphans = Vec::new();
+
+        for &face in visible.iter() {
+            for (a, b) in faces[face].edges() {
+                let neighbor_visible = edges
+                    .get(&(b, a))
+                    .is_some_and(|neighbor| visible_set.contains(neighbor));
+                if !neighbor_visible {
+                    horizon.push((a, b));
+                }
+            }
+        }
+
+        for &face in visible.iter() {
+            faces[face].alive = false;
+            orphans.append(&mut faces[face].outside);
+            for edge in faces[face].edges() {
+                edges.remove(&edge);
+            }
+        }
+
+        let first_new = faces.len();
+        for (a, b) in horizon {
+            let face = HullFace::new(points, [a, b, apex]);
+            for edge in face.edges() {
+                edges.insert(edge, faces.len());
+            }
+            faces.push(face);
+        }
+
+        for point in orphans {
+            if point == apex {
+                continue;
+            }
+            if let Some(face) = faces[first_new..]
+                .iter_mut()
+                .find(|face| face.distance(points[point]) > epsilon)
+            {
+                face.outside.push(point);
+            }
+        }
+
+        stack.extend(first_new..faces.len());
+    }
+
+    faces
+        .into_iter()
+        .filter(|face| face.alive)
+        .map(|face| face.vertices)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn down() -> Vec3 {
+        vec3(0.0, -1.0, 0.0)
+    }
+
+    fn model(points: &[Vec3], triangles: &[[u32; 3]], transform: Mat4) -> Vec<MeshWithTransform> {
+        vec![MeshWithTransform {
+            mesh: CpuMesh {
+                positions: Positions::F32(points.to_vec()),
+                indices: Indices::U32(triangles.iter().flatten().copied().collect()),
+                ..Default::default()
+            },
+            transform,
+            color: None,
+        }]
+    }
+
+    fn box_model(size: Vec3, transform: Mat4) -> Vec<MeshWithTransform> {
+        // Corner `i` sits at the far side of x, y and z for bits 0, 1 and 2 of `i`
+        let points: Vec<Vec3> = (0..8)
+            .map(|i| {
+                vec3(
+                    (i & 1) as f32 * size.x,
+                    ((i >> 1) & 1) as f32 * size.y,
+                    ((i >> 2) & 1) as f32 * size.z,
+                )
+            })
+            .collect();
+        let quads = [
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+        ];
+        let triangles: Vec<[u32; 3]> = quads
+            .iter()
+            .flat_map(|[a, b, c, d]| [[*a, *b, *c], [*a, *c, *d]])
+            .collect();
+
+        model(&points, &triangles, transform)
+    }
+
+    // Right angled 4 x 4 triangle extruded by 1.6, the slope is the largest face but the model sits lower on a triangle
+    fn wedge() -> Vec<MeshWithTransform> {
+        let points = [
+            vec3(0.0, 0.0, 0.0),
+            vec3(4.0, 0.0, 0.0),
+            vec3(0.0, 4.0, 0.0),
+            vec3(0.0, 0.0, 1.6),
+            vec3(4.0, 0.0, 1.6),
+            vec3(0.0, 4.0, 1.6),
+        ];
+        let triangles = [
+            [0, 2, 1],
+            [3, 4, 5],
+            [0, 1, 4],
+            [0, 4, 3],
+            [0, 3, 5],
+            [0, 5, 2],
+            [1, 2, 5],
+            [1, 5, 4],
+        ];
+
+        model(&points, &triangles, Mat4::identity())
+    }
+
+    /// Lays the model down and returns its height above the bed and the number of vertices touching the bed.
+    fn rest(meshes: &[MeshWithTransform], strategy: AutoOrient) -> (f32, usize) {
+        let rotation = resting_rotation(meshes, down(), strategy);
+        let depths: Vec<f32> = meshes
+            .iter()
+            .flat_map(|mesh| {
+                let transform = rotation * mesh.transform;
+                mesh.mesh
+                    .positions
+                    .to_f32()
+                    .into_iter()
+                    .map(move |point| (transform * point.extend(1.0)).truncate().dot(down()))
+            })
+            .collect();
+
+        let bed = depths.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let top = depths.iter().cloned().fold(f32::INFINITY, f32::min);
+        let touching = depths.iter().filter(|depth| bed - **depth < 1e-4).count();
+
+        (bed - top, touching)
+    }
+
+    #[test]
+    fn cube_rests_on_a_face() {
+        let cube = box_model(vec3(2.0, 2.0, 2.0), Mat4::identity());
+
+        for strategy in [AutoOrient::LargestFace, AutoOrient::LowestCenter] {
+            let (height, touching) = rest(&cube, strategy);
+            assert!((height - 2.0).abs() < 1e-4, "{strategy:?} height {height}");
+            assert_eq!(touching, 4, "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn tilted_box_returns_to_its_largest_face() {
+        let tilt = Mat4::from_translation(vec3(5.0, -3.0, 2.0))
+            * Mat4::from_angle_x(Deg(35.0))
+            * Mat4::from_angle_z(Deg(20.0));
+        let slab = box_model(vec3(4.0, 2.0, 1.0), tilt);
+
+        for strategy in [AutoOrient::LargestFace, AutoOrient::LowestCenter] {
+            let (height, touching) = rest(&slab, strategy);
+            assert!((height - 1.0).abs() < 1e-4, "{strategy:?} height {height}");
+            assert_eq!(touching, 4, "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn wedge_strategies_pick_different_faces() {
+        let wedge = wedge();
+
+        // On the slope the height is the distance of the right angle edge from it
+        let (height, touching) = rest(&wedge, AutoOrient::LargestFace);
+        assert!((height - 8.0f32.sqrt()).abs() < 1e-4, "height {height}");
+        assert_eq!(touching, 4);
+
+        let (height, touching) = rest(&wedge, AutoOrient::LowestCenter);
+        assert!((height - 1.6).abs() < 1e-4, "height {height}");
+        assert_eq!(touching, 3);
+    }
+
+    #[test]
+    fn flat_and_empty_input_keeps_the_orientation() {
+        let square = model(
+            &[
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+                vec3(0.0, 1.0, 0.0),
+            ],
+            &[[0, 1, 2], [0, 2, 3]],
+            Mat4::from_angle_y(Deg(30.0)),
+        );
+        let line = model(
+            &[
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 1.0, 1.0),
+                vec3(2.0, 2.0, 2.0),
+                vec3(3.0, 3.0, 3.0),
+            ],
+            &[[0, 1, 2], [1, 2, 3]],
+            Mat4::identity(),
+        );
+        let triangle = model(
+            &[
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(0.0, 0.0, 1.0),
+            ],
+            &[[0, 1, 2]],
+            Mat4::identity(),
+        );
+
+        for meshes in [square, line, triangle, Vec::new()] {
+            for strategy in [AutoOrient::LargestFace, AutoOrient::LowestCenter] {
+                assert_eq!(
+                    resting_rotation(&meshes, down(), strategy),
+                    Mat4::identity()
+                );
+            }
+        }
+    }
+}
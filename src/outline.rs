@@ -0,0 +1,72 @@
+use crate::screen_space::GBuffer;
+
+///
+/// Draws silhouette and crease edges over a rendered image.
+/// A pixel is an edge when it borders the background or when the surface normal changes by more than `crease_angle` degrees towards one of its neighbours.
+///
+pub fn draw_outlines(
+    pixels: &mut [[u8; 4]],
+    gbuffer: &GBuffer,
+    color: [u8; 4],
+    line_width: f32,
+    crease_angle: f32,
+) {
+    let crease_cos = crease_angle.to_radians().cos();
+    let mut edges = vec![false; gbuffer.width * gbuffer.height];
+
+    for y in 0..gbuffer.height {
+        for x in 0..gbuffer.width {
+            let i = y * gbuffer.width + x;
+            let normal = match (gbuffer.is_covered(i), gbuffer.normal(i)) {
+                (true, Some(normal)) => normal,
+                (true, None) => {
+                    edges[i] = true;
+                    continue;
+                }
+                _ => continue,
+            };
+
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let neighbour = match gbuffer.offset_index(x, y, dx, dy) {
+                    Some(j) => j,
+                    None => continue,
+                };
+
+                // Creases are only tested forwards so a single pixel wide line is marked
+                let is_edge = match (gbuffer.is_covered(neighbour), gbuffer.normal(neighbour)) {
+                    (false, _) => true,
+                    (true, Some(other)) => dx + dy > 0 && normal.dot(other) < crease_cos,
+                    (true, None) => false,
+                };
+
+                if is_edge {
+                    edges[i] = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    let radius = (line_width / 2.0).max(0.5);
+    let reach = radius.ceil() as isize;
+
+    for y in 0..gbuffer.height {
+        for x in 0..gbuffer.width {
+            if !edges[y * gbuffer.width + x] {
+                continue;
+            }
+
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    if ((dx * dx + dy * dy) as f32) > radius * radius {
+                        continue;
+                    }
+
+                    if let Some(j) = gbuffer.offset_index(x, y, dx, dy) {
+                        pixels[j] = color;
+                    }
+                }
+            }
+        }
+    }
+}
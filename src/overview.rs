@@ -0,0 +1,217 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use zip::ZipArchive;
+
+use crate::ThumbnailError;
+use crate::contact_sheet;
+use crate::text::{draw_text, text_width};
+
+const MODEL_EXTENSIONS: [&str; 7] = [
+    ".stl",
+    ".3mf",
+    ".obj",
+    ".gcode",
+    ".stl.zip",
+    ".obj.zip",
+    ".gcode.zip",
+];
+
+const LABEL_COLOR: [u8; 4] = [255, 255, 255, 255];
+const LABEL_HALO_COLOR: [u8; 4] = [0, 0, 0, 200];
+
+// Distinguishes the extraction folders of overviews generated at the same time
+static EXTRACTION_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Model files collected for an overview with the label shown under their tile.
+/// Members of zip archives are extracted to a temporary folder, which is removed again on drop.
+pub struct OverviewInputs {
+    pub files: Vec<(PathBuf, String)>,
+    extraction_dirs: Vec<PathBuf>,
+}
+
+impl Drop for OverviewInputs {
+    fn drop(&mut self) {
+        for dir in self.extraction_dirs.iter() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Whether `name` has an extension [crate::parse_mesh::parse_file] understands.
+pub fn is_model_file(name: &str) -> bool {
+    MODEL_EXTENSIONS
+        .iter()
+        .any(|extension| name.ends_with(extension))
+}
+
+// Any other zip is treated as an archive of separate models
+fn is_archive(name: &str) -> bool {
+    name.ends_with(".zip") && !is_model_file(name)
+}
+
+///
+/// Collects the model files among `paths` in a stable order.
+/// Directories are searched recursively, zip archives are extracted and other files are taken as they are if they are models.
+///
+pub fn collect_inputs(paths: &[PathBuf]) -> Result<OverviewInputs, ThumbnailError> {
+    let mut inputs = OverviewInputs {
+        files: Vec::new(),
+        extraction_dirs: Vec::new(),
+    };
+
+    for path in paths {
+        if path.is_dir() {
+            collect_directory(path, &mut inputs)?;
+        } else {
+            collect_file(path, &mut inputs)?;
+        }
+    }
+
+    Ok(inputs)
+}
+
+fn collect_directory(dir: &Path, inputs: &mut OverviewInputs) -> Result<(), ThumbnailError> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<PathBuf>, std::io::Error>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            collect_directory(&entry, inputs)?;
+        } else {
+            collect_file(&entry, inputs)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn collect_file(path: &Path, inputs: &mut OverviewInputs) -> Result<(), ThumbnailError> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+
+    if is_archive(name) {
+        extract_archive(path, inputs)
+    } else {
+        if is_model_file(name) {
+            inputs.files.push((path.to_path_buf(), name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn extract_archive(path: &Path, inputs: &mut OverviewInputs) -> Result<(), ThumbnailError> {
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+    let dir = std::env::temp_dir().join(format!(
+        "mesh-thumbnail-{}-{}",
+        std::process::id(),
+        EXTRACTION_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir)?;
+    inputs.extraction_dirs.push(dir.clone());
+
+    let mut members = Vec::new();
+
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let entry_name = file.name().to_string();
+        let name = entry_name
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+
+        if file.is_dir() || !is_model_file(&name) {
+            continue;
+        }
+
+        // Prefix with the index, members with the same name may live in different folders of the archive
+        let target = dir.join(format!("{:04}-{}", i, name));
+        std::io::copy(&mut file, &mut File::create(&target)?)?;
+        members.push((entry_name, target, name));
+    }
+
+    members.sort();
+    inputs
+        .files
+        .extend(members.into_iter().map(|(_, target, name)| (target, name)));
+
+    Ok(())
+}
+
+/// Height of the strip under each tile holding its label.
+fn label_band(tile_size: u32) -> u32 {
+    (tile_size as f32 * 0.12).max(14.0).round() as u32
+}
+
+///
+/// Lays out square tiles of `tile_size` pixels in a grid with the label of each tile written below it.
+/// Returns the pixels of the overview together with its width and height.
+///
+pub fn compose_overview(
+    tiles: Vec<Vec<[u8; 4]>>,
+    labels: &[String],
+    tile_size: u32,
+    columns: u32,
+    background: [u8; 4],
+) -> (Vec<[u8; 4]>, u32, u32) {
+    let band = label_band(tile_size);
+    let cell = (tile_size, tile_size + band);
+    let count = tiles.len();
+
+    let cells = tiles
+        .into_iter()
+        .map(|mut tile| {
+            tile.resize(((tile_size + band) * tile_size) as usize, background);
+            tile
+        })
+        .collect();
+
+    let mut pixels = contact_sheet::compose_sheet(cells, None, cell, columns, background);
+    let (width, height) = contact_sheet::sheet_size(count, columns, cell.0, cell.1);
+    let columns_used = contact_sheet::grid_columns(count, columns);
+    let size = band as f32 * 0.6;
+
+    for (index, label) in labels.iter().enumerate().take(count) {
+        let label = fit_label(label, tile_size as f32 * 0.95, size);
+        let left = (index % columns_used) as f32 * cell.0 as f32;
+        let top = (index / columns_used) as f32 * cell.1 as f32 + tile_size as f32;
+        let x = left + (tile_size as f32 - text_width(&label, size)) / 2.0;
+
+        draw_text(
+            &mut pixels,
+            width as usize,
+            &label,
+            (x, top + (band as f32 - size * 1.2) / 2.0),
+            size,
+            LABEL_COLOR,
+            Some(LABEL_HALO_COLOR),
+        );
+    }
+
+    (pixels, width, height)
+}
+
+// Shortens `label` with an ellipsis until it fits into `max_width` pixels
+fn fit_label(label: &str, max_width: f32, size: f32) -> String {
+    if text_width(label, size) <= max_width {
+        return label.to_string();
+    }
+
+    let mut characters: Vec<char> = label.chars().collect();
+
+    while !characters.is_empty() {
+        characters.pop();
+        let shortened: String = characters.iter().chain(['…'].iter()).collect();
+
+        if text_width(&shortened, size) <= max_width {
+            return shortened;
+        }
+    }
+
+    String::from("…")
+}
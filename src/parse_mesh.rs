@@ -12,6 +12,8 @@ use wavefront_obj::obj::{self, ObjSet};
 use zip::ZipArchive;
 use zip::result::ZipError;
 
+use crate::geometry::{convex_outline, outline_bounds};
+
 #[derive(Clone)]
 pub struct MeshWithTransform {
     pub mesh: CpuMesh,
@@ -21,6 +23,8 @@ pub struct MeshWithTransform {
 
 pub struct ParseResult {
     pub meshes: Vec<MeshWithTransform>,
+    bed_shape: Option<Vec<Vec2>>,
+    unit_scale: Option<f32>,
 }
 
 impl ParseResult {
@@ -31,11 +35,37 @@ impl ParseResult {
                 transform: Mat4::identity(),
                 color: None,
             }],
+            bed_shape: None,
+            unit_scale: None,
         }
     }
 
     pub fn multiple(meshes: Vec<MeshWithTransform>) -> Self {
-        ParseResult { meshes }
+        ParseResult {
+            meshes,
+            bed_shape: None,
+            unit_scale: None,
+        }
+    }
+
+    pub fn with_bed_shape(mut self, bed_shape: Option<Vec<Vec2>>) -> Self {
+        self.bed_shape = bed_shape;
+        self
+    }
+
+    /// Outline of the printable area in millimeters, when the file carries a printer profile.
+    pub fn bed_shape(&self) -> Option<&[Vec2]> {
+        self.bed_shape.as_deref()
+    }
+
+    pub fn with_unit_scale(mut self, unit_scale: Option<f32>) -> Self {
+        self.unit_scale = unit_scale;
+        self
+    }
+
+    /// Millimeters per unit of the mesh coordinates, when the file declares its unit.
+    pub fn unit_scale(&self) -> Option<f32> {
+        self.unit_scale
     }
 }
 
@@ -96,6 +126,14 @@ impl From<ParseFloatError> for ParseError {
 }
 
 pub fn parse_file(path: &str) -> Result<ParseResult, ParseError> {
+    parse_file_with_normals(path, false)
+}
+
+///
+/// Like `parse_file`, but also reads the vertex normals stored in OBJ files when `use_normals` is set.
+/// They are skipped otherwise, since they split shared vertices for nothing.
+///
+pub fn parse_file_with_normals(path: &str, use_normals: bool) -> Result<ParseResult, ParseError> {
     if path.ends_with(".stl") {
         return Ok(ParseResult::single(parse_stl(path)?));
     } else if path.ends_with(".3mf") {
@@ -103,13 +141,13 @@ pub fn parse_file(path: &str) -> Result<ParseResult, ParseError> {
     } else if path.ends_with(".stl.zip") {
         return Ok(ParseResult::single(parse_stl_zip(path)?));
     } else if path.ends_with(".obj") {
-        return Ok(ParseResult::single(parse_obj(path)?));
+        return parse_obj(path, use_normals);
     } else if path.ends_with(".obj.zip") {
-        return Ok(ParseResult::single(parse_obj_zip(path)?));
+        return parse_obj_zip(path, use_normals);
     } else if path.ends_with(".gcode") {
-        return Ok(ParseResult::single(parse_gcode(path)?));
+        return parse_gcode(path);
     } else if path.ends_with(".gcode.zip") {
-        return Ok(ParseResult::single(parse_gcode_zip(path)?));
+        return parse_gcode_zip(path);
     }
 
     return Err(ParseError::ParseError(String::from("Unknown file type")));
@@ -270,7 +308,11 @@ fn parse_3mf(path: &str) -> Result<ParseResult, ParseError> {
         }
     }
 
-    Ok(ParseResult::multiple(result_meshes))
+    let (bed_shape, unit_scale) = extract_bed_shape_and_unit_from_3mf(path);
+
+    Ok(ParseResult::multiple(result_meshes)
+        .with_bed_shape(bed_shape)
+        .with_unit_scale(unit_scale))
 }
 
 // Extract extruder colors from Slic3r_PE.config in 3MF archive
@@ -312,6 +354,124 @@ fn extract_extruder_colors_from_3mf(path: &str) -> Vec<Srgba> {
     colors
 }
 
+// Relationship type of the root model in the package relationships of a 3MF archive
+const MODEL_RELATIONSHIP: &str = "http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel";
+
+// Extract the printable area from the PrusaSlicer or Bambu Studio config and the unit of the root model in one pass over a 3MF archive
+fn extract_bed_shape_and_unit_from_3mf(path: &str) -> (Option<Vec<Vec2>>, Option<f32>) {
+    let Some(mut zip) = File::open(path)
+        .ok()
+        .and_then(|file| ZipArchive::new(file).ok())
+    else {
+        return (None, None);
+    };
+
+    let mut rels = String::new();
+    if let Ok(mut file) = zip.by_name("_rels/.rels") {
+        let _ = file.read_to_string(&mut rels);
+    }
+    let root_model = root_model_name(&rels);
+
+    let mut bed_shape = None;
+    let mut unit_scale = None;
+
+    for i in 0..zip.len() {
+        if let Ok(mut file) = zip.by_index(i) {
+            let name = file.name().to_string();
+
+            if name.eq_ignore_ascii_case(&root_model) {
+                // The model element opens the document, no need to read the whole mesh
+                let mut header = Vec::new();
+                if (&mut file).take(4096).read_to_end(&mut header).is_ok() {
+                    unit_scale = parse_model_unit(&String::from_utf8_lossy(&header));
+                }
+                continue;
+            }
+
+            if bed_shape.is_some()
+                || (name != "Metadata/Slic3r_PE.config"
+                    && name != "Metadata/project_settings.config")
+            {
+                continue;
+            }
+
+            let mut content = String::new();
+            if file.read_to_string(&mut content).is_err() {
+                continue;
+            }
+
+            if name == "Metadata/Slic3r_PE.config" {
+                // Parse bed_shape line
+                bed_shape = content
+                    .lines()
+                    .find(|line| line.starts_with("; bed_shape ="))
+                    .and_then(|line| line.split('=').nth(1))
+                    .and_then(parse_bed_shape);
+            } else {
+                // project_settings.config is JSON, the points are stored as a list of strings
+                let regex = Regex::new(r#""printable_area"\s*:\s*\[([^\]]*)\]"#).unwrap();
+                bed_shape = regex.captures(&content).and_then(|caps| {
+                    parse_bed_shape(&caps.get(1).unwrap().as_str().replace('"', ""))
+                });
+            }
+        }
+    }
+
+    (bed_shape, unit_scale)
+}
+
+// Path of the root model inside the archive, producers may name it freely as long as the package relationships point to it
+fn root_model_name(rels: &str) -> String {
+    let relationship = Regex::new(r#"<Relationship\b[^>]*>"#).unwrap();
+    let target = Regex::new(r#"\bTarget\s*=\s*"/?([^"]+)""#).unwrap();
+
+    relationship
+        .find_iter(rels)
+        .map(|element| element.as_str())
+        .filter(|element| element.contains(MODEL_RELATIONSHIP))
+        .find_map(|element| Some(target.captures(element)?.get(1)?.as_str().to_string()))
+        .unwrap_or_else(|| String::from("3D/3dmodel.model"))
+}
+
+// Millimeters per unit from the unit attribute of the model element, 3MF coordinates are in millimeters unless it says otherwise
+fn parse_model_unit(header: &str) -> Option<f32> {
+    let regex = Regex::new(r#"<model\b[^>]*\bunit\s*=\s*"([a-z]+)""#).unwrap();
+
+    match regex.captures(header)?.get(1)?.as_str() {
+        "micron" => Some(0.001),
+        "millimeter" => Some(1.0),
+        "centimeter" => Some(10.0),
+        "inch" => Some(25.4),
+        "foot" => Some(304.8),
+        "meter" => Some(1000.0),
+        _ => None,
+    }
+}
+
+// Parse a slicer bed shape like "0x0,250x0,250x210,0x210" into its outline points, non-convex shapes become their bounding rectangle
+fn parse_bed_shape(value: &str) -> Option<Vec<Vec2>> {
+    let points = value
+        .split(',')
+        .map(|point| {
+            let (x, y) = point.trim().split_once('x')?;
+            Some(vec2(x.trim().parse().ok()?, y.trim().parse().ok()?))
+        })
+        .collect::<Option<Vec<Vec2>>>()?;
+
+    if points.len() < 3 {
+        return None;
+    }
+
+    let outline = convex_outline(points);
+    let (min, max) = outline_bounds(&outline);
+
+    if max.x > min.x && max.y > min.y {
+        Some(outline)
+    } else {
+        None
+    }
+}
+
 #[derive(Clone)]
 struct VolumeInfo {
     first_triangle_id: usize,
@@ -519,16 +679,16 @@ fn parse_stl_zip(path: &str) -> Result<CpuMesh, ParseError> {
     )));
 }
 
-fn parse_obj(path: &str) -> Result<CpuMesh, ParseError> {
+fn parse_obj(path: &str, use_normals: bool) -> Result<ParseResult, ParseError> {
     let mut handle = File::open(path)?;
     let mut buffer = Vec::new();
     handle.read_to_end(&mut buffer)?;
 
     let obj = obj::parse(std::str::from_utf8(&buffer).unwrap())?;
-    parse_obj_inner(&obj)
+    parse_obj_inner(&obj, use_normals)
 }
 
-fn parse_obj_zip(path: &str) -> Result<CpuMesh, ParseError> {
+fn parse_obj_zip(path: &str, use_normals: bool) -> Result<ParseResult, ParseError> {
     let handle = File::open(path)?;
     let mut zip = ZipArchive::new(handle)?;
 
@@ -538,9 +698,10 @@ fn parse_obj_zip(path: &str) -> Result<CpuMesh, ParseError> {
             let mut buffer = Vec::with_capacity(file.size() as usize);
             file.read_to_end(&mut buffer)?;
 
-            return Ok(parse_obj_inner(&obj::parse(
-                std::str::from_utf8(&buffer).unwrap(),
-            )?)?);
+            return Ok(parse_obj_inner(
+                &obj::parse(std::str::from_utf8(&buffer).unwrap())?,
+                use_normals,
+            )?);
         }
     }
 
@@ -575,24 +736,30 @@ fn parse_stl_inner(stl: &IndexedMesh) -> Result<CpuMesh, ParseError> {
 }
 
 // https://github.com/asny/three-d-asset/blob/main/src/io/obj.rs#L54
-fn parse_obj_inner(obj: &ObjSet) -> Result<CpuMesh, ParseError> {
-    let mut all_meshes: Vec<CpuMesh> = obj
+// Every object of the file becomes its own part, in the order of the file
+fn parse_obj_inner(obj: &ObjSet, use_normals: bool) -> Result<ParseResult, ParseError> {
+    let meshes: Vec<MeshWithTransform> = obj
         .objects
         .iter()
-        .map(|object| {
+        .filter_map(|object| {
             let mut positions = Vec::new();
+            let mut normals = Vec::new();
             let mut indices = Vec::new();
             for mesh in object.geometry.iter() {
-                let mut map: HashMap<usize, usize> = HashMap::new();
+                // Vertices are split per normal so the normals from the file survive
+                let mut map: HashMap<(usize, Option<usize>), usize> = HashMap::new();
 
                 let mut process = |i: wavefront_obj::obj::VTNIndex| {
-                    let mut index = map.get(&i.0).map(|v| *v);
+                    let normal_index = i.2.filter(|_| use_normals);
+                    let mut index = map.get(&(i.0, normal_index)).map(|v| *v);
 
                     if index.is_none() {
                         index = Some(positions.len());
-                        map.insert(i.0, index.unwrap());
+                        map.insert((i.0, normal_index), index.unwrap());
                         let position = object.vertices[i.0];
                         positions.push(Vector3::new(position.x, position.y, position.z));
+                        let normal = normal_index.and_then(|n| object.normals.get(n));
+                        normals.push(normal.map(|n| vec3(n.x as f32, n.y as f32, n.z as f32)));
                     }
 
                     indices.push(index.unwrap() as u32);
@@ -610,29 +777,36 @@ fn parse_obj_inner(obj: &ObjSet) -> Result<CpuMesh, ParseError> {
                 }
             }
 
-            CpuMesh {
-                positions: Positions::F64(positions),
-                indices: Indices::U32(indices),
-                ..Default::default()
+            if indices.is_empty() {
+                return None;
             }
+
+            // Only use the normals when every vertex has one
+            let normals = normals
+                .into_iter()
+                .collect::<Option<Vec<Vec3>>>()
+                .filter(|normals| !normals.is_empty());
+
+            Some(MeshWithTransform {
+                mesh: CpuMesh {
+                    positions: Positions::F64(positions),
+                    indices: Indices::U32(indices),
+                    normals,
+                    ..Default::default()
+                },
+                transform: Mat4::identity(),
+                color: None,
+            })
         })
         .collect();
 
-    all_meshes.sort_by(|a, b| a.indices.len().cmp(&b.indices.len()).reverse());
-
-    if all_meshes.len() <= 0 {
+    if meshes.is_empty() {
         return Err(ParseError::MeshConvertError(String::from(
-            "No meshes found in 3mf model",
+            "No meshes found in obj model",
         )));
     }
 
-    let mesh = &all_meshes[0];
-
-    return Ok(CpuMesh {
-        positions: mesh.positions.clone(),
-        indices: mesh.indices.clone(),
-        ..Default::default()
-    });
+    Ok(ParseResult::multiple(meshes))
 }
 
 struct Point {
@@ -640,13 +814,13 @@ struct Point {
     use_line: bool,
 }
 
-fn parse_gcode(path: &str) -> Result<CpuMesh, ParseError> {
+fn parse_gcode(path: &str) -> Result<ParseResult, ParseError> {
     let mut handle = File::open(path)?;
 
     parse_gcode_inner(&mut handle)
 }
 
-fn parse_gcode_zip(path: &str) -> Result<CpuMesh, ParseError> {
+fn parse_gcode_zip(path: &str) -> Result<ParseResult, ParseError> {
     let handle = File::open(path)?;
     let mut zip = ZipArchive::new(handle)?;
 
@@ -665,7 +839,7 @@ fn parse_gcode_zip(path: &str) -> Result<CpuMesh, ParseError> {
         "Failed to find .stl model in zip",
     )));
 }
-fn parse_gcode_inner<W>(reader: &mut W) -> Result<CpuMesh, ParseError>
+fn parse_gcode_inner<W>(reader: &mut W) -> Result<ParseResult, ParseError>
 where
     W: Read,
 {
@@ -678,10 +852,18 @@ where
     let regex_xy_no_extrusion = Regex::new(r"X([\d.]+)\s+Y([\d.]+)").unwrap();
     let regex_z = Regex::new(r"Z([\d.]+)").unwrap();
     let mut position_unsafe = false;
+    let mut bed_shape = None;
 
     for line in reader.lines() {
         let line = line?;
-        if line.starts_with("G1") || line.starts_with("G0") {
+        if line.starts_with(";") {
+            // PrusaSlicer writes bed_shape, Orca and Bambu Studio write printable_area
+            if let Some((key, value)) = line[1..].split_once('=') {
+                if key.trim() == "bed_shape" || key.trim() == "printable_area" {
+                    bed_shape = parse_bed_shape(value).or(bed_shape);
+                }
+            }
+        } else if line.starts_with("G1") || line.starts_with("G0") {
             if let Some(caps) = regex_z.captures(&line) {
                 last_z = caps.get(1).unwrap().as_str().parse::<f32>()?;
             }
@@ -743,11 +925,12 @@ where
         indices.extend(cylinder.indices.into_u32().unwrap().iter().map(|i| *i + l));
     }
 
-    return Ok(CpuMesh {
+    return Ok(ParseResult::single(CpuMesh {
         positions: Positions::F32(positions.clone()),
         indices: Indices::U32(indices.clone()),
         ..Default::default()
-    });
+    })
+    .with_bed_shape(bed_shape));
 }
 
 // Smart code from https://github.com/asny/three-d/blob/master/examples/wireframe/src/main.rs
@@ -760,3 +943,374 @@ fn edge_transform(p1: Vec3, p2: Vec3) -> Mat4 {
         ))
         * Mat4::from_nonuniform_scale((p1 - p2).magnitude(), 0.2, 0.4)
 }
+
+///
+/// Computes angle weighted vertex normals for `mesh`.
+/// Faces only contribute to the normal of a shared vertex when they meet at less than `crease_angle` degrees, otherwise the vertex is split so the edge stays hard.
+///
+pub fn compute_smooth_normals(mesh: &CpuMesh, crease_angle: f32) -> CpuMesh {
+    let positions = mesh.positions.to_f32();
+    let indices = mesh
+        .indices
+        .to_u32()
+        .unwrap_or_else(|| (0..positions.len() as u32).collect());
+    let crease_cos = crease_angle.to_radians().cos();
+
+    let face_normals: Vec<Vec3> = indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let p0 = positions[triangle[0] as usize];
+            let normal =
+                (positions[triangle[1] as usize] - p0).cross(positions[triangle[2] as usize] - p0);
+
+            if normal.magnitude2() > 0.0 {
+                normal.normalize()
+            } else {
+                vec3(0.0, 0.0, 0.0)
+            }
+        })
+        .collect();
+
+    let mut vertex_faces: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+    for (face, triangle) in indices.chunks_exact(3).enumerate() {
+        for vertex in triangle {
+            vertex_faces[*vertex as usize].push(face);
+        }
+    }
+
+    // Interior angle of `face` at `vertex`, used as the weight of the face normal
+    let corner_angle = |face: usize, vertex: u32| {
+        let triangle = &indices[face * 3..face * 3 + 3];
+        let corner = triangle.iter().position(|v| *v == vertex).unwrap_or(0);
+        let p0 = positions[triangle[corner] as usize];
+        let e1 = positions[triangle[(corner + 1) % 3] as usize] - p0;
+        let e2 = positions[triangle[(corner + 2) % 3] as usize] - p0;
+
+        if e1.magnitude2() == 0.0 || e2.magnitude2() == 0.0 {
+            return 0.0;
+        }
+
+        e1.normalize().dot(e2.normalize()).clamp(-1.0, 1.0).acos()
+    };
+
+    let mut new_positions = Vec::new();
+    let mut new_normals = Vec::new();
+    let mut new_indices = Vec::with_capacity(indices.len());
+    let mut map: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+
+    for (face, triangle) in indices.chunks_exact(3).enumerate() {
+        for vertex in triangle {
+            let face_normal = face_normals[face];
+            let mut normal = vertex_faces[*vertex as usize]
+                .iter()
+                .filter(|other| face_normals[**other].dot(face_normal) >= crease_cos)
+                .map(|other| face_normals[*other] * corner_angle(*other, *vertex))
+                .fold(vec3(0.0, 0.0, 0.0), |sum, normal| sum + normal);
+
+            normal = if normal.magnitude2() > 0.0 {
+                normal.normalize()
+            } else {
+                face_normal
+            };
+
+            // Corners that end up with the same normal share a vertex again
+            let key = (
+                *vertex,
+                [normal.x.to_bits(), normal.y.to_bits(), normal.z.to_bits()],
+            );
+            let index = *map.entry(key).or_insert_with(|| {
+                new_positions.push(positions[*vertex as usize]);
+                new_normals.push(normal);
+                new_positions.len() as u32 - 1
+            });
+            new_indices.push(index);
+        }
+    }
+
+    CpuMesh {
+        positions: Positions::F32(new_positions),
+        indices: Indices::U32(new_indices),
+        normals: Some(new_normals),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mesh(positions: Vec<Vec3>, triangles: &[[u32; 3]]) -> CpuMesh {
+        CpuMesh {
+            positions: Positions::F32(positions),
+            indices: Indices::U32(triangles.iter().flatten().copied().collect()),
+            ..Default::default()
+        }
+    }
+
+    fn unit_cube() -> CpuMesh {
+        // Corner `i` sits at x = bit 0, y = bit 1 and z = bit 2 of `i`
+        let positions = (0..8)
+            .map(|i| vec3((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32))
+            .collect();
+        let quads = [
+            [0, 4, 6, 2],
+            [1, 3, 7, 5],
+            [0, 1, 5, 4],
+            [2, 6, 7, 3],
+            [0, 2, 3, 1],
+            [4, 5, 7, 6],
+        ];
+        let triangles: Vec<[u32; 3]> = quads
+            .iter()
+            .flat_map(|[a, b, c, d]| [[*a, *b, *c], [*a, *c, *d]])
+            .collect();
+
+        mesh(positions, &triangles)
+    }
+
+    fn icosphere(subdivisions: u32) -> CpuMesh {
+        let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+        let mut positions: Vec<Vec3> = [
+            (-1.0, t, 0.0),
+            (1.0, t, 0.0),
+            (-1.0, -t, 0.0),
+            (1.0, -t, 0.0),
+            (0.0, -1.0, t),
+            (0.0, 1.0, t),
+            (0.0, -1.0, -t),
+            (0.0, 1.0, -t),
+            (t, 0.0, -1.0),
+            (t, 0.0, 1.0),
+            (-t, 0.0, -1.0),
+            (-t, 0.0, 1.0),
+        ]
+        .iter()
+        .map(|(x, y, z)| vec3(*x, *y, *z).normalize())
+        .collect();
+        let mut triangles: Vec<[u32; 3]> = vec![
+            [0, 11, 5],
+            [0, 5, 1],
+            [0, 1, 7],
+            [0, 7, 10],
+            [0, 10, 11],
+            [1, 5, 9],
+            [5, 11, 4],
+            [11, 10, 2],
+            [10, 7, 6],
+            [7, 1, 8],
+            [3, 9, 4],
+            [3, 4, 2],
+            [3, 2, 6],
+            [3, 6, 8],
+            [3, 8, 9],
+            [4, 9, 5],
+            [2, 4, 11],
+            [6, 2, 10],
+            [8, 6, 7],
+            [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+            let mut midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    let middle = (positions[a as usize] + positions[b as usize]) / 2.0;
+                    positions.push(middle.normalize());
+                    positions.len() as u32 - 1
+                })
+            };
+
+            triangles = triangles
+                .iter()
+                .flat_map(|[a, b, c]| {
+                    let ab = midpoint(*a, *b);
+                    let bc = midpoint(*b, *c);
+                    let ca = midpoint(*c, *a);
+                    [[*a, ab, ca], [*b, bc, ab], [*c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        mesh(positions, &triangles)
+    }
+
+    #[test]
+    fn smooth_normals_keep_cube_edges_hard() {
+        let smoothed = compute_smooth_normals(&unit_cube(), 30.0);
+        let positions = smoothed.positions.to_f32();
+        let normals = smoothed.normals.unwrap();
+        let indices = smoothed.indices.to_u32().unwrap();
+
+        // Every corner is split into one vertex per side
+        assert_eq!(positions.len(), 24);
+
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+            let face_normal = (b - a).cross(c - a).normalize();
+
+            for vertex in triangle {
+                assert!(normals[*vertex as usize].dot(face_normal) > 0.999);
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_normals_join_cube_corners_above_the_crease_angle() {
+        let smoothed = compute_smooth_normals(&unit_cube(), 120.0);
+        let positions = smoothed.positions.to_f32();
+        let normals = smoothed.normals.unwrap();
+
+        assert_eq!(positions.len(), 8);
+
+        for (position, normal) in positions.iter().zip(normals.iter()) {
+            let diagonal = (position - vec3(0.5, 0.5, 0.5)).normalize();
+            assert!(normal.dot(diagonal) > 0.999);
+        }
+    }
+
+    #[test]
+    fn smooth_normals_round_a_subdivided_sphere() {
+        let sphere = icosphere(3);
+        let vertex_count = sphere.positions.len();
+        let smoothed = compute_smooth_normals(&sphere, 30.0);
+        let positions = smoothed.positions.to_f32();
+        let normals = smoothed.normals.unwrap();
+
+        // No vertex is split, every one shares a single normal pointing away from the center
+        assert_eq!(positions.len(), vertex_count);
+
+        for (position, normal) in positions.iter().zip(normals.iter()) {
+            assert!(normal.dot(position.normalize()) > 0.999);
+        }
+    }
+
+    #[test]
+    fn bed_shape_parses_slicer_outlines() {
+        assert_eq!(
+            parse_bed_shape("0x0,250x0,250x210,0x210"),
+            Some(vec![
+                vec2(0.0, 0.0),
+                vec2(250.0, 0.0),
+                vec2(250.0, 210.0),
+                vec2(0.0, 210.0),
+            ])
+        );
+        assert_eq!(
+            parse_bed_shape(" -90.5x-90.5 , 90.5x-90.5, 0x 90.5"),
+            Some(vec![vec2(-90.5, -90.5), vec2(90.5, -90.5), vec2(0.0, 90.5)])
+        );
+    }
+
+    #[test]
+    fn bed_shape_rejects_malformed_values() {
+        for value in [
+            "",
+            "0x0",
+            "0x0,250x0",
+            "0x0,250,250x210,0x210",
+            "0x0,250x0,250xdeep,0x210",
+            "0x0;250x0;250x210;0x210",
+            "0x0,250x0,250x210,",
+            "0x0,100x0,200x0",
+        ] {
+            assert_eq!(parse_bed_shape(value), None, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn bed_shape_replaces_concave_outlines_by_their_bounds() {
+        assert_eq!(
+            parse_bed_shape("0x0,200x0,200x100,100x100,100x200,0x200"),
+            Some(vec![
+                vec2(0.0, 0.0),
+                vec2(200.0, 0.0),
+                vec2(200.0, 200.0),
+                vec2(0.0, 200.0),
+            ])
+        );
+    }
+
+    #[test]
+    fn root_model_follows_the_package_relationships() {
+        let rels = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Target="/Metadata/thumbnail.png" Id="rel-2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/thumbnail"/>
+  <Relationship Target="/3D/Model.model" Id="rel-1" Type="http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel"/>
+</Relationships>"#;
+
+        assert_eq!(root_model_name(rels), "3D/Model.model");
+        assert_eq!(root_model_name(""), "3D/3dmodel.model");
+    }
+
+    #[test]
+    fn model_unit_converts_to_millimeters() {
+        let header =
+            |unit: &str| format!(r#"<?xml version="1.0"?><model unit="{unit}" xml:lang="en-US">"#);
+
+        assert_eq!(parse_model_unit(&header("inch")), Some(25.4));
+        assert_eq!(parse_model_unit(&header("micron")), Some(0.001));
+        assert_eq!(parse_model_unit(&header("furlong")), None);
+        assert_eq!(parse_model_unit(r#"<model xml:lang="en-US">"#), None);
+    }
+
+    const FOLDED_OBJ: &str = "o folded
+v 0 0 0
+v 1 0 0
+v 0 1 0
+v 0 0 1
+vn 0 0 1
+vn 0 1 0
+f 1//1 2//1 3//1
+f 1//2 4//2 2//2
+";
+
+    fn parse_single_obj(source: &str, use_normals: bool) -> CpuMesh {
+        let mut result = parse_obj_inner(&obj::parse(source).unwrap(), use_normals).unwrap();
+        assert_eq!(result.meshes.len(), 1);
+
+        result.meshes.remove(0).mesh
+    }
+
+    #[test]
+    fn obj_vertices_are_shared_without_normals() {
+        let mesh = parse_single_obj(FOLDED_OBJ, false);
+
+        assert_eq!(mesh.positions.len(), 4);
+        assert!(mesh.normals.is_none());
+    }
+
+    #[test]
+    fn obj_vertices_are_split_per_normal() {
+        let mesh = parse_single_obj(FOLDED_OBJ, true);
+
+        assert_eq!(mesh.positions.len(), 6);
+        assert_eq!(mesh.normals.map(|normals| normals.len()), Some(6));
+    }
+
+    #[test]
+    fn obj_objects_become_parts_in_file_order() {
+        let source = "o small
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f 1 2 3
+o empty
+v 5 5 5
+o large
+v 0 0 0
+v 2 0 0
+v 0 2 0
+v 2 2 0
+f 5 6 7
+f 6 8 7
+";
+        let result = parse_obj_inner(&obj::parse(source).unwrap(), false).unwrap();
+        let triangles: Vec<usize> = result
+            .meshes
+            .iter()
+            .map(|part| part.mesh.indices.len().unwrap() / 3)
+            .collect();
+
+        assert_eq!(triangles, vec![1, 2]);
+    }
+}
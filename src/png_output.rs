@@ -0,0 +1,298 @@
+use clap::ValueEnum;
+use color_quant::NeuQuant;
+use image::RgbaImage;
+use std::collections::HashMap;
+
+use crate::ThumbnailError;
+use crate::surface_maps::DepthImage;
+
+/// Trade off between encoding speed and size of PNG output.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+impl ToString for PngCompression {
+    fn to_string(&self) -> String {
+        match self {
+            PngCompression::Fast => String::from("fast"),
+            PngCompression::Default => String::from("default"),
+            PngCompression::Best => String::from("best"),
+        }
+    }
+}
+
+///
+/// Encodes `image` as PNG, either in full color or quantized to a palette of at most `palette` colors.
+/// Palettes keep the alpha of every entry, so anti-aliased edges against a transparent background survive.
+///
+pub fn encode_png(
+    image: &RgbaImage,
+    compression: PngCompression,
+    palette: Option<u16>,
+) -> Result<Vec<u8>, ThumbnailError> {
+    let mut buffer = Vec::new();
+    let error = |e: png::EncodingError| ThumbnailError::Other(e.to_string());
+
+    {
+        let mut encoder = png::Encoder::new(&mut buffer, image.width(), image.height());
+        encoder.set_compression(match compression {
+            PngCompression::Fast => png::Compression::Fast,
+            PngCompression::Default => png::Compression::Default,
+            PngCompression::Best => png::Compression::Best,
+        });
+
+        let data = match palette {
+            Some(colors) => {
+                let (palette, alphas, depth, data) = quantize(image, colors);
+                encoder.set_color(png::ColorType::Indexed);
+                encoder.set_depth(depth);
+                encoder.set_palette(palette);
+                encoder.set_trns(alphas);
+                // Filtering rarely helps indexed images
+                encoder.set_filter(png::FilterType::NoFilter);
+                data
+            }
+            None => {
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                if compression != PngCompression::Fast {
+                    encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+                }
+                image.as_raw().clone()
+            }
+        };
+
+        let mut writer = encoder.write_header().map_err(error)?;
+        writer.write_image_data(&data).map_err(error)?;
+        writer.finish().map_err(error)?;
+    }
+
+    Ok(buffer)
+}
+
+///
+/// Reduces `image` to at most `colors` colors.
+/// Images with no more colors than that keep them exactly, fully transparent pixels always share one transparent entry.
+/// Returns the RGB palette, the alpha of each palette entry, the smallest bit depth holding every index and the packed rows.
+///
+fn quantize(image: &RgbaImage, colors: u16) -> (Vec<u8>, Vec<u8>, png::BitDepth, Vec<u8>) {
+    let colors = colors.clamp(2, 256) as usize;
+    let (color_map, indices) = match exact_palette(image, colors) {
+        Some(exact) => exact,
+        None => neuquant_palette(image, colors),
+    };
+
+    let palette = color_map
+        .iter()
+        .flat_map(|color| [color[0], color[1], color[2]])
+        .collect();
+    let alphas = color_map.iter().map(|color| color[3]).collect();
+
+    let (depth, bits) = match colors {
+        2 => (png::BitDepth::One, 1),
+        3..=4 => (png::BitDepth::Two, 2),
+        5..=16 => (png::BitDepth::Four, 4),
+        _ => (png::BitDepth::Eight, 8),
+    };
+
+    let width = image.width() as usize;
+    let row_bytes = (width * bits).div_ceil(8);
+    let mut data = vec![0u8; row_bytes * image.height() as usize];
+
+    for (i, index) in indices.into_iter().enumerate() {
+        let (x, y) = (i % width, i / width);
+        // Pixels are packed from the most significant bit on
+        let bit = x * bits;
+        let shift = 8 - bits - bit % 8;
+        data[y * row_bytes + bit / 8] |= index << shift;
+    }
+
+    (palette, alphas, depth, data)
+}
+
+/// Palette of the distinct colors of `image` and the index of every pixel, `None` when there are more than `colors`.
+fn exact_palette(image: &RgbaImage, colors: usize) -> Option<(Vec<[u8; 4]>, Vec<u8>)> {
+    let mut color_map: Vec<[u8; 4]> = Vec::new();
+    let mut lookup: HashMap<[u8; 4], u8> = HashMap::new();
+    let mut indices = Vec::with_capacity(image.len() / 4);
+
+    for pixel in image.pixels() {
+        let index = match lookup.get(&pixel.0) {
+            Some(index) => *index,
+            None if color_map.len() < colors => {
+                color_map.push(pixel.0);
+                lookup.insert(pixel.0, (color_map.len() - 1) as u8);
+                (color_map.len() - 1) as u8
+            }
+            None => return None,
+        };
+        indices.push(index);
+    }
+
+    Some((color_map, indices))
+}
+
+///
+/// Palette learned by NeuQuant from the visible pixels, with a separate entry for fully transparent pixels.
+/// Every entry is then moved to the mean of the pixels using it, so entries only used by opaque pixels stay opaque.
+///
+fn neuquant_palette(image: &RgbaImage, colors: usize) -> (Vec<[u8; 4]>, Vec<u8>) {
+    let reserved = image.pixels().any(|pixel| pixel[3] == 0) as usize;
+    let visible: Vec<u8> = image
+        .pixels()
+        .filter(|pixel| pixel[3] > 0)
+        .flat_map(|pixel| pixel.0)
+        .collect();
+    let quantizer = NeuQuant::new(10, colors - reserved, &visible);
+
+    let indices: Vec<u8> = image
+        .pixels()
+        .map(|pixel| match pixel[3] {
+            0 => 0,
+            _ => (reserved + quantizer.index_of(&pixel.0)) as u8,
+        })
+        .collect();
+
+    let mut sums = vec![[0u64; 5]; colors];
+    for (pixel, index) in image.pixels().zip(indices.iter()) {
+        let sum = &mut sums[*index as usize];
+        for channel in 0..4 {
+            sum[channel] += pixel[channel] as u64;
+        }
+        sum[4] += 1;
+    }
+
+    let learned = quantizer.color_map_rgba();
+    let color_map = sums
+        .iter()
+        .enumerate()
+        .map(|(index, sum)| match (sum[4], index.checked_sub(reserved)) {
+            (0, None) => [0, 0, 0, 0],
+            (0, Some(entry)) => learned[entry * 4..entry * 4 + 4].try_into().unwrap(),
+            (count, _) => [0, 1, 2, 3].map(|channel| ((sum[channel] + count / 2) / count) as u8),
+        })
+        .collect();
+
+    (color_map, indices)
+}
+
+/// Encodes a 16 bit grayscale image, like a depth map, as PNG.
+pub fn encode_gray16(
+    image: &DepthImage,
+    compression: PngCompression,
+) -> Result<Vec<u8>, ThumbnailError> {
+    let mut buffer = Vec::new();
+    let error = |e: png::EncodingError| ThumbnailError::Other(e.to_string());
+
+    {
+        let mut encoder = png::Encoder::new(&mut buffer, image.width(), image.height());
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Sixteen);
+        encoder.set_compression(match compression {
+            PngCompression::Fast => png::Compression::Fast,
+            PngCompression::Default => png::Compression::Default,
+            PngCompression::Best => png::Compression::Best,
+        });
+
+        // PNG stores samples big endian
+        let data: Vec<u8> = image
+            .as_raw()
+            .iter()
+            .flat_map(|sample| sample.to_be_bytes())
+            .collect();
+
+        let mut writer = encoder.write_header().map_err(error)?;
+        writer.write_image_data(&data).map_err(error)?;
+        writer.finish().map_err(error)?;
+    }
+
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// Odd sized image with a transparent border around `opaque` distinct opaque colors.
+    fn bordered(opaque: usize) -> RgbaImage {
+        RgbaImage::from_fn(37, 23, |x, y| {
+            if x < 3 || y < 3 || x > 33 || y > 19 {
+                return Rgba([0, 0, 0, 0]);
+            }
+
+            let i = ((y * 37 + x) as usize % opaque) as u32;
+            Rgba([
+                (i * 97 % 256) as u8,
+                (i * 53 % 256) as u8,
+                (i * 191 % 256) as u8,
+                255,
+            ])
+        })
+    }
+
+    fn decode(data: &[u8]) -> (png::ColorType, png::BitDepth, RgbaImage) {
+        let mut decoder = png::Decoder::new(std::io::Cursor::new(data));
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().unwrap();
+        let (color_type, bit_depth) = (reader.info().color_type, reader.info().bit_depth);
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut buffer).unwrap();
+        buffer.truncate(frame.buffer_size());
+
+        let image = RgbaImage::from_raw(frame.width, frame.height, buffer).unwrap();
+        (color_type, bit_depth, image)
+    }
+
+    const PALETTES: [(u16, png::BitDepth); 4] = [
+        (2, png::BitDepth::One),
+        (4, png::BitDepth::Two),
+        (16, png::BitDepth::Four),
+        (256, png::BitDepth::Eight),
+    ];
+
+    #[test]
+    fn palette_round_trips_images_with_few_colors() {
+        for (colors, depth) in PALETTES {
+            let image = bordered(colors as usize - 1);
+            let data = encode_png(&image, PngCompression::Default, Some(colors)).unwrap();
+            let (color_type, bit_depth, decoded) = decode(&data);
+
+            assert_eq!(color_type, png::ColorType::Indexed, "{colors} colors");
+            assert_eq!(bit_depth, depth, "{colors} colors");
+            assert_eq!(decoded.dimensions(), image.dimensions(), "{colors} colors");
+            assert!(decoded == image, "{colors} colors");
+        }
+    }
+
+    #[test]
+    fn palette_keeps_transparent_and_opaque_pixels() {
+        for (colors, depth) in PALETTES {
+            let image = bordered(300);
+            let data = encode_png(&image, PngCompression::Default, Some(colors)).unwrap();
+            let (color_type, bit_depth, decoded) = decode(&data);
+
+            assert_eq!(color_type, png::ColorType::Indexed, "{colors} colors");
+            assert_eq!(bit_depth, depth, "{colors} colors");
+            assert_eq!(decoded.dimensions(), image.dimensions(), "{colors} colors");
+
+            for (original, quantized) in image.pixels().zip(decoded.pixels()) {
+                assert_eq!(original[3], quantized[3], "{colors} colors");
+            }
+        }
+    }
+
+    #[test]
+    fn palette_of_a_transparent_image() {
+        let image = RgbaImage::new(5, 3);
+        let data = encode_png(&image, PngCompression::Fast, Some(16)).unwrap();
+        let (_, _, decoded) = decode(&data);
+
+        assert!(decoded == image);
+    }
+}
@@ -9,8 +9,11 @@ use pyo3::types::{PyAny, PyBytes, PyModule};
 use pyo3::{create_exception, wrap_pyfunction};
 
 use crate::{
-    Format, ThumbnailError, ThumbnailOptions, generate_thumbnail_bytes_for_file,
-    generate_thumbnail_for_file,
+    AnimationFormat, AutoOrient, BedShape, ColorMode, DimensionUnits, EncodedImage, Format,
+    GifPalette, LightingRig, MaterialPreset, ModelUnits, PngCompression, Projection, RenderMode,
+    SectionAxis, ThumbnailError, ThumbnailOptions, ThumbnailSelection, UpAxis, ViewPreset,
+    generate_images_for_file, generate_overview_bytes_for_paths, generate_overview_for_paths,
+    generate_thumbnail_bytes_for_file, generate_thumbnail_for_file,
 };
 
 create_exception!(python, PyThumbnailError, PyException);
@@ -39,12 +42,158 @@ pub struct PyThumbnailOptions {
     prefer_gcode_thumbnail: bool,
     #[pyo3(get, set)]
     inverse_zoom: f32,
+    thumbnail_selection: String,
+    #[pyo3(get, set)]
+    letterbox_thumbnail: bool,
+    projection: String,
+    view: Option<String>,
+    #[pyo3(get, set)]
+    auto_frame: bool,
+    #[pyo3(get, set)]
+    frame_margin: f32,
+    #[pyo3(get, set)]
+    auto_crop: bool,
+    #[pyo3(get, set)]
+    crop_margin: f32,
+    antialias: u32,
+    #[pyo3(get, set)]
+    outline: bool,
+    #[pyo3(get, set)]
+    outline_color: String,
+    #[pyo3(get, set)]
+    outline_width: f32,
+    #[pyo3(get, set)]
+    crease_angle: f32,
+    render_mode: String,
+    lighting: String,
+    material: String,
+    #[pyo3(get, set)]
+    ambient: Option<f32>,
+    #[pyo3(get, set)]
+    specular: Option<f32>,
+    #[pyo3(get, set)]
+    ambient_occlusion: bool,
+    #[pyo3(get, set)]
+    ao_strength: f32,
+    #[pyo3(get, set)]
+    ground_shadow: bool,
+    #[pyo3(get, set)]
+    shadow_strength: f32,
+    #[pyo3(get, set)]
+    smooth_normals: bool,
+    #[pyo3(get, set)]
+    smooth_angle: f32,
+    #[pyo3(get, set)]
+    build_plate: bool,
+    #[pyo3(get, set)]
+    grid_spacing: f32,
+    bed_shape: String,
+    #[pyo3(get, set)]
+    bed_width: Option<f32>,
+    #[pyo3(get, set)]
+    bed_depth: Option<f32>,
+    #[pyo3(get, set)]
+    dimensions: bool,
+    #[pyo3(get, set)]
+    dimension_lines: bool,
+    dimension_units: String,
+    animation: Option<String>,
+    #[pyo3(get, set)]
+    frame_rate: f32,
+    #[pyo3(get, set)]
+    loop_count: u32,
+    gif_palette: String,
+    contact_sheet: Vec<String>,
+    #[pyo3(get, set)]
+    sheet_columns: u32,
+    #[pyo3(get, set)]
+    sheet_labels: bool,
+    #[pyo3(get, set)]
+    tile_size: u32,
+    #[pyo3(get, set)]
+    overview_columns: u32,
+    #[pyo3(get, set)]
+    explode: f32,
+    section: Option<String>,
+    #[pyo3(get, set)]
+    section_normal: Option<(f32, f32, f32)>,
+    #[pyo3(get, set)]
+    section_offset: f32,
+    #[pyo3(get, set)]
+    section_color: String,
+    #[pyo3(get, set)]
+    overhangs: bool,
+    #[pyo3(get, set)]
+    overhang_angle: f32,
+    #[pyo3(get, set)]
+    overhang_color: String,
+    #[pyo3(get, set)]
+    bed_contact_color: String,
+    color_mode: String,
+    auto_orient: Option<String>,
+    up_axis: String,
+    units: String,
+    #[pyo3(get, set)]
+    quality: u8,
+    #[pyo3(get, set)]
+    lossless: bool,
+    #[pyo3(get, set)]
+    jpeg_quality: u8,
+    png_compression: String,
+    #[pyo3(get, set)]
+    png_palette: Option<u16>,
+    #[pyo3(get, set)]
+    images_per_file: u32,
+    #[pyo3(get, set)]
+    depth_map: bool,
+    #[pyo3(get, set)]
+    normal_map: bool,
+}
+
+/// An encoded image with its size, file extension, view index, source ("rendered", "3mf" or "gcode") and kind ("color", "depth" or "normal").
+#[pyclass(name = "ThumbnailImage")]
+pub struct PyThumbnailImage {
+    data: Vec<u8>,
+    #[pyo3(get)]
+    width: u32,
+    #[pyo3(get)]
+    height: u32,
+    #[pyo3(get)]
+    extension: String,
+    #[pyo3(get)]
+    view: u32,
+    #[pyo3(get)]
+    source: String,
+    #[pyo3(get)]
+    kind: String,
+}
+
+#[pymethods]
+impl PyThumbnailImage {
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, &self.data)
+    }
+}
+
+impl From<EncodedImage> for PyThumbnailImage {
+    fn from(image: EncodedImage) -> Self {
+        Self {
+            data: image.data,
+            width: image.width,
+            height: image.height,
+            extension: image.extension,
+            view: image.view,
+            source: image.source.to_string(),
+            kind: image.kind.to_string(),
+        }
+    }
 }
 
 #[pymethods]
 impl PyThumbnailOptions {
     #[new]
-    #[pyo3(signature = (rotatex=0.0, rotatey=0.0, width=512, height=512, format="png", color="DDDDDD", overwrite=false, fallback_3mf_thumbnail=false, prefer_3mf_thumbnail=false, prefer_gcode_thumbnail=false, inverse_zoom=1.0))]
+    #[pyo3(signature = (rotatex=0.0, rotatey=0.0, width=512, height=512, format="png", color="DDDDDD", overwrite=false, fallback_3mf_thumbnail=false, prefer_3mf_thumbnail=false, prefer_gcode_thumbnail=false, inverse_zoom=1.0, thumbnail_selection="main", letterbox_thumbnail=false, projection="perspective", view=None, auto_frame=false, frame_margin=5.0, auto_crop=false, crop_margin=5.0, antialias=1, outline=false, outline_color="000000", outline_width=1.0, crease_angle=40.0, render_mode="shaded", lighting="headlight", material="matte", ambient=None, specular=None, ambient_occlusion=false, ao_strength=1.0, ground_shadow=false, shadow_strength=0.5, smooth_normals=false, smooth_angle=30.0, build_plate=false, grid_spacing=10.0, bed_shape="rectangle", bed_width=None, bed_depth=None, dimensions=false, dimension_lines=false, dimension_units="mm", animation=None, frame_rate=15.0, loop_count=0, gif_palette="global", contact_sheet=Vec::new(), sheet_columns=0, sheet_labels=false, tile_size=256, overview_columns=0, explode=0.0, section=None, section_normal=None, section_offset=0.0, section_color="E0452C", overhangs=false, overhang_angle=45.0, overhang_color="E02020", bed_contact_color="2E7DD7", color_mode="single", auto_orient=None, up_axis="auto", units="auto", quality=80, lossless=false, jpeg_quality=90, png_compression="default", png_palette=None, images_per_file=1, depth_map=false, normal_map=false))]
     fn new(
         rotatex: f32,
         rotatey: f32,
@@ -57,6 +206,68 @@ impl PyThumbnailOptions {
         prefer_3mf_thumbnail: bool,
         prefer_gcode_thumbnail: bool,
         inverse_zoom: f32,
+        thumbnail_selection: &str,
+        letterbox_thumbnail: bool,
+        projection: &str,
+        view: Option<&str>,
+        auto_frame: bool,
+        frame_margin: f32,
+        auto_crop: bool,
+        crop_margin: f32,
+        antialias: u32,
+        outline: bool,
+        outline_color: &str,
+        outline_width: f32,
+        crease_angle: f32,
+        render_mode: &str,
+        lighting: &str,
+        material: &str,
+        ambient: Option<f32>,
+        specular: Option<f32>,
+        ambient_occlusion: bool,
+        ao_strength: f32,
+        ground_shadow: bool,
+        shadow_strength: f32,
+        smooth_normals: bool,
+        smooth_angle: f32,
+        build_plate: bool,
+        grid_spacing: f32,
+        bed_shape: &str,
+        bed_width: Option<f32>,
+        bed_depth: Option<f32>,
+        dimensions: bool,
+        dimension_lines: bool,
+        dimension_units: &str,
+        animation: Option<&str>,
+        frame_rate: f32,
+        loop_count: u32,
+        gif_palette: &str,
+        contact_sheet: Vec<String>,
+        sheet_columns: u32,
+        sheet_labels: bool,
+        tile_size: u32,
+        overview_columns: u32,
+        explode: f32,
+        section: Option<&str>,
+        section_normal: Option<(f32, f32, f32)>,
+        section_offset: f32,
+        section_color: &str,
+        overhangs: bool,
+        overhang_angle: f32,
+        overhang_color: &str,
+        bed_contact_color: &str,
+        color_mode: &str,
+        auto_orient: Option<&str>,
+        up_axis: &str,
+        units: &str,
+        quality: u8,
+        lossless: bool,
+        jpeg_quality: u8,
+        png_compression: &str,
+        png_palette: Option<u16>,
+        images_per_file: u32,
+        depth_map: bool,
+        normal_map: bool,
     ) -> PyResult<Self> {
         Ok(Self {
             rotatex,
@@ -70,6 +281,71 @@ impl PyThumbnailOptions {
             prefer_3mf_thumbnail,
             prefer_gcode_thumbnail,
             inverse_zoom,
+            thumbnail_selection: normalize_thumbnail_selection_string(thumbnail_selection)?,
+            letterbox_thumbnail,
+            projection: normalize_projection_string(projection)?,
+            view: view.map(normalize_view_string).transpose()?,
+            auto_frame,
+            frame_margin,
+            auto_crop,
+            crop_margin,
+            antialias: validate_antialias(antialias)?,
+            outline,
+            outline_color: outline_color.to_string(),
+            outline_width,
+            crease_angle,
+            render_mode: normalize_render_mode_string(render_mode)?,
+            lighting: normalize_lighting_string(lighting)?,
+            material: normalize_material_string(material)?,
+            ambient,
+            specular,
+            ambient_occlusion,
+            ao_strength,
+            ground_shadow,
+            shadow_strength,
+            smooth_normals,
+            smooth_angle,
+            build_plate,
+            grid_spacing,
+            bed_shape: normalize_bed_shape_string(bed_shape)?,
+            bed_width,
+            bed_depth,
+            dimensions,
+            dimension_lines,
+            dimension_units: normalize_dimension_units_string(dimension_units)?,
+            animation: animation.map(normalize_animation_string).transpose()?,
+            frame_rate,
+            loop_count,
+            gif_palette: normalize_gif_palette_string(gif_palette)?,
+            contact_sheet: contact_sheet
+                .iter()
+                .map(|view| normalize_view_string(view))
+                .collect::<PyResult<Vec<String>>>()?,
+            sheet_columns,
+            sheet_labels,
+            tile_size,
+            overview_columns,
+            explode,
+            section: section.map(normalize_section_string).transpose()?,
+            section_normal,
+            section_offset,
+            section_color: section_color.to_string(),
+            overhangs,
+            overhang_angle,
+            overhang_color: overhang_color.to_string(),
+            bed_contact_color: bed_contact_color.to_string(),
+            color_mode: normalize_color_mode_string(color_mode)?,
+            auto_orient: auto_orient.map(normalize_auto_orient_string).transpose()?,
+            up_axis: normalize_up_axis_string(up_axis)?,
+            units: normalize_units_string(units)?,
+            quality,
+            lossless,
+            jpeg_quality,
+            png_compression: normalize_png_compression_string(png_compression)?,
+            png_palette,
+            images_per_file,
+            depth_map,
+            normal_map,
         })
     }
 
@@ -84,6 +360,207 @@ impl PyThumbnailOptions {
         Ok(())
     }
 
+    #[getter]
+    fn thumbnail_selection(&self) -> String {
+        self.thumbnail_selection.clone()
+    }
+
+    #[setter]
+    fn set_thumbnail_selection(&mut self, value: &str) -> PyResult<()> {
+        self.thumbnail_selection = normalize_thumbnail_selection_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn projection(&self) -> String {
+        self.projection.clone()
+    }
+
+    #[setter]
+    fn set_projection(&mut self, value: &str) -> PyResult<()> {
+        self.projection = normalize_projection_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn view(&self) -> Option<String> {
+        self.view.clone()
+    }
+
+    #[setter]
+    fn set_view(&mut self, value: Option<&str>) -> PyResult<()> {
+        self.view = value.map(normalize_view_string).transpose()?;
+        Ok(())
+    }
+
+    #[getter]
+    fn antialias(&self) -> u32 {
+        self.antialias
+    }
+
+    #[setter]
+    fn set_antialias(&mut self, value: u32) -> PyResult<()> {
+        self.antialias = validate_antialias(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn render_mode(&self) -> String {
+        self.render_mode.clone()
+    }
+
+    #[setter]
+    fn set_render_mode(&mut self, value: &str) -> PyResult<()> {
+        self.render_mode = normalize_render_mode_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn lighting(&self) -> String {
+        self.lighting.clone()
+    }
+
+    #[setter]
+    fn set_lighting(&mut self, value: &str) -> PyResult<()> {
+        self.lighting = normalize_lighting_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn material(&self) -> String {
+        self.material.clone()
+    }
+
+    #[setter]
+    fn set_material(&mut self, value: &str) -> PyResult<()> {
+        self.material = normalize_material_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn bed_shape(&self) -> String {
+        self.bed_shape.clone()
+    }
+
+    #[setter]
+    fn set_bed_shape(&mut self, value: &str) -> PyResult<()> {
+        self.bed_shape = normalize_bed_shape_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn dimension_units(&self) -> String {
+        self.dimension_units.clone()
+    }
+
+    #[setter]
+    fn set_dimension_units(&mut self, value: &str) -> PyResult<()> {
+        self.dimension_units = normalize_dimension_units_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn animation(&self) -> Option<String> {
+        self.animation.clone()
+    }
+
+    #[setter]
+    fn set_animation(&mut self, value: Option<&str>) -> PyResult<()> {
+        self.animation = value.map(normalize_animation_string).transpose()?;
+        Ok(())
+    }
+
+    #[getter]
+    fn gif_palette(&self) -> String {
+        self.gif_palette.clone()
+    }
+
+    #[setter]
+    fn set_gif_palette(&mut self, value: &str) -> PyResult<()> {
+        self.gif_palette = normalize_gif_palette_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn section(&self) -> Option<String> {
+        self.section.clone()
+    }
+
+    #[setter]
+    fn set_section(&mut self, value: Option<&str>) -> PyResult<()> {
+        self.section = value.map(normalize_section_string).transpose()?;
+        Ok(())
+    }
+
+    #[getter]
+    fn color_mode(&self) -> String {
+        self.color_mode.clone()
+    }
+
+    #[setter]
+    fn set_color_mode(&mut self, value: &str) -> PyResult<()> {
+        self.color_mode = normalize_color_mode_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn auto_orient(&self) -> Option<String> {
+        self.auto_orient.clone()
+    }
+
+    #[setter]
+    fn set_auto_orient(&mut self, value: Option<&str>) -> PyResult<()> {
+        self.auto_orient = value.map(normalize_auto_orient_string).transpose()?;
+        Ok(())
+    }
+
+    #[getter]
+    fn up_axis(&self) -> String {
+        self.up_axis.clone()
+    }
+
+    #[setter]
+    fn set_up_axis(&mut self, value: &str) -> PyResult<()> {
+        self.up_axis = normalize_up_axis_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn units(&self) -> String {
+        self.units.clone()
+    }
+
+    #[setter]
+    fn set_units(&mut self, value: &str) -> PyResult<()> {
+        self.units = normalize_units_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn png_compression(&self) -> String {
+        self.png_compression.clone()
+    }
+
+    #[setter]
+    fn set_png_compression(&mut self, value: &str) -> PyResult<()> {
+        self.png_compression = normalize_png_compression_string(value)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn contact_sheet(&self) -> Vec<String> {
+        self.contact_sheet.clone()
+    }
+
+    #[setter]
+    fn set_contact_sheet(&mut self, value: Vec<String>) -> PyResult<()> {
+        self.contact_sheet = value
+            .iter()
+            .map(|view| normalize_view_string(view))
+            .collect::<PyResult<Vec<String>>>()?;
+        Ok(())
+    }
+
     fn copy(&self) -> Self {
         self.clone()
     }
@@ -93,11 +570,203 @@ fn normalize_format_string(value: &str) -> PyResult<String> {
     match value.to_ascii_lowercase().as_str() {
         "png" => Ok(String::from("png")),
         "jpg" | "jpeg" => Ok(String::from("jpg")),
-        _ => Err(PyValueError::new_err("format must be 'png' or 'jpg'")),
+        "webp" => Ok(String::from("webp")),
+        "avif" => Ok(String::from("avif")),
+        "bmp" => Ok(String::from("bmp")),
+        "tiff" | "tif" => Ok(String::from("tiff")),
+        "qoi" => Ok(String::from("qoi")),
+        _ => Err(PyValueError::new_err(
+            "format must be 'png', 'jpg', 'webp', 'avif', 'bmp', 'tiff' or 'qoi'",
+        )),
+    }
+}
+
+fn validate_antialias(value: u32) -> PyResult<u32> {
+    if !(1..=8).contains(&value) {
+        return Err(PyValueError::new_err("antialias must be between 1 and 8"));
+    }
+
+    Ok(value)
+}
+
+fn normalize_thumbnail_selection_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().replace('_', "-").as_str() {
+        "main" => Ok(String::from("main")),
+        "largest" => Ok(String::from("largest")),
+        "smallest-fitting" => Ok(String::from("smallest-fitting")),
+        "closest-aspect" => Ok(String::from("closest-aspect")),
+        _ => Err(PyValueError::new_err(
+            "thumbnail_selection must be 'main', 'largest', 'smallest-fitting' or 'closest-aspect'",
+        )),
+    }
+}
+
+fn normalize_projection_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().as_str() {
+        "perspective" => Ok(String::from("perspective")),
+        "orthographic" | "ortho" => Ok(String::from("orthographic")),
+        _ => Err(PyValueError::new_err(
+            "projection must be 'perspective' or 'orthographic'",
+        )),
+    }
+}
+
+fn normalize_view_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().as_str() {
+        "front" | "back" | "left" | "right" | "top" | "bottom" | "iso" | "dimetric" => {
+            Ok(value.to_ascii_lowercase())
+        }
+        "isometric" => Ok(String::from("iso")),
+        _ => Err(PyValueError::new_err(
+            "view must be one of 'front', 'back', 'left', 'right', 'top', 'bottom', 'iso' or 'dimetric'",
+        )),
+    }
+}
+
+fn normalize_render_mode_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().replace('_', "-").as_str() {
+        "shaded" => Ok(String::from("shaded")),
+        "wireframe" => Ok(String::from("wireframe")),
+        "shaded-wireframe" => Ok(String::from("shaded-wireframe")),
+        "points" => Ok(String::from("points")),
+        _ => Err(PyValueError::new_err(
+            "render_mode must be 'shaded', 'wireframe', 'shaded_wireframe' or 'points'",
+        )),
+    }
+}
+
+fn normalize_lighting_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().replace('_', "-").as_str() {
+        "headlight" => Ok(String::from("headlight")),
+        "studio" => Ok(String::from("studio")),
+        "top-down" => Ok(String::from("top-down")),
+        _ => Err(PyValueError::new_err(
+            "lighting must be 'headlight', 'studio' or 'top_down'",
+        )),
+    }
+}
+
+fn normalize_material_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().replace('_', "-").as_str() {
+        "matte" => Ok(String::from("matte")),
+        "glossy-plastic" => Ok(String::from("glossy-plastic")),
+        "metal" => Ok(String::from("metal")),
+        "resin" => Ok(String::from("resin")),
+        _ => Err(PyValueError::new_err(
+            "material must be 'matte', 'glossy_plastic', 'metal' or 'resin'",
+        )),
+    }
+}
+
+fn normalize_bed_shape_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().as_str() {
+        "rectangle" => Ok(String::from("rectangle")),
+        "circle" => Ok(String::from("circle")),
+        _ => Err(PyValueError::new_err(
+            "bed_shape must be 'rectangle' or 'circle'",
+        )),
+    }
+}
+
+fn normalize_dimension_units_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().as_str() {
+        "mm" => Ok(String::from("mm")),
+        "cm" => Ok(String::from("cm")),
+        "inch" | "in" => Ok(String::from("inch")),
+        _ => Err(PyValueError::new_err(
+            "dimension_units must be 'mm', 'cm' or 'inch'",
+        )),
+    }
+}
+
+fn normalize_color_mode_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().replace('_', "-").as_str() {
+        "single" => Ok(String::from("single")),
+        "height" => Ok(String::from("height")),
+        "part" => Ok(String::from("part")),
+        "file-name" => Ok(String::from("file-name")),
+        _ => Err(PyValueError::new_err(
+            "color_mode must be 'single', 'height', 'part' or 'file_name'",
+        )),
+    }
+}
+
+fn normalize_auto_orient_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().replace('_', "-").as_str() {
+        "largest-face" => Ok(String::from("largest-face")),
+        "lowest-center" => Ok(String::from("lowest-center")),
+        _ => Err(PyValueError::new_err(
+            "auto_orient must be 'largest_face' or 'lowest_center'",
+        )),
+    }
+}
+
+fn normalize_up_axis_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().as_str() {
+        "auto" => Ok(String::from("auto")),
+        "y" => Ok(String::from("y")),
+        "z" => Ok(String::from("z")),
+        _ => Err(PyValueError::new_err("up_axis must be 'auto', 'y' or 'z'")),
+    }
+}
+
+fn normalize_units_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().as_str() {
+        "auto" => Ok(String::from("auto")),
+        "mm" => Ok(String::from("mm")),
+        "cm" => Ok(String::from("cm")),
+        "m" => Ok(String::from("m")),
+        "inch" | "in" => Ok(String::from("inch")),
+        _ => Err(PyValueError::new_err(
+            "units must be 'auto', 'mm', 'cm', 'm' or 'inch'",
+        )),
+    }
+}
+
+fn normalize_png_compression_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().as_str() {
+        "fast" => Ok(String::from("fast")),
+        "default" => Ok(String::from("default")),
+        "best" => Ok(String::from("best")),
+        _ => Err(PyValueError::new_err(
+            "png_compression must be 'fast', 'default' or 'best'",
+        )),
     }
 }
 
-fn py_options_to_rust(options: Option<PyRef<'_, PyThumbnailOptions>>) -> PyResult<ThumbnailOptions> {
+fn normalize_section_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().as_str() {
+        "x" => Ok(String::from("x")),
+        "y" => Ok(String::from("y")),
+        "z" => Ok(String::from("z")),
+        _ => Err(PyValueError::new_err("section must be 'x', 'y' or 'z'")),
+    }
+}
+
+fn normalize_animation_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().as_str() {
+        "gif" => Ok(String::from("gif")),
+        "apng" => Ok(String::from("apng")),
+        "webp" => Ok(String::from("webp")),
+        _ => Err(PyValueError::new_err(
+            "animation must be 'gif', 'apng' or 'webp'",
+        )),
+    }
+}
+
+fn normalize_gif_palette_string(value: &str) -> PyResult<String> {
+    match value.to_ascii_lowercase().replace('_', "-").as_str() {
+        "global" => Ok(String::from("global")),
+        "per-frame" => Ok(String::from("per-frame")),
+        _ => Err(PyValueError::new_err(
+            "gif_palette must be 'global' or 'per_frame'",
+        )),
+    }
+}
+
+fn py_options_to_rust(
+    options: Option<PyRef<'_, PyThumbnailOptions>>,
+) -> PyResult<ThumbnailOptions> {
     let mut rust_options = ThumbnailOptions::default();
 
     if let Some(opts) = options {
@@ -112,9 +781,87 @@ fn py_options_to_rust(options: Option<PyRef<'_, PyThumbnailOptions>>) -> PyResul
         rust_options.prefer_gcode_thumbnail = opts.prefer_gcode_thumbnail;
         rust_options.inverse_zoom = opts.inverse_zoom;
         rust_options.format = format_from_string(&opts.format)?;
+        rust_options.thumbnail_selection =
+            thumbnail_selection_from_string(&opts.thumbnail_selection)?;
+        rust_options.letterbox_thumbnail = opts.letterbox_thumbnail;
+        rust_options.projection = projection_from_string(&opts.projection)?;
+        rust_options.view = opts.view.as_deref().map(view_from_string).transpose()?;
+        rust_options.auto_frame = opts.auto_frame;
+        rust_options.frame_margin = opts.frame_margin;
+        rust_options.auto_crop = opts.auto_crop;
+        rust_options.crop_margin = opts.crop_margin;
+        rust_options.antialias = opts.antialias;
+        rust_options.outline = opts.outline;
+        rust_options.outline_color = opts.outline_color.clone();
+        rust_options.outline_width = opts.outline_width;
+        rust_options.crease_angle = opts.crease_angle;
+        rust_options.render_mode = render_mode_from_string(&opts.render_mode)?;
+        rust_options.lighting = lighting_from_string(&opts.lighting)?;
+        rust_options.material = material_from_string(&opts.material)?;
+        rust_options.ambient = opts.ambient;
+        rust_options.specular = opts.specular;
+        rust_options.ambient_occlusion = opts.ambient_occlusion;
+        rust_options.ao_strength = opts.ao_strength;
+        rust_options.ground_shadow = opts.ground_shadow;
+        rust_options.shadow_strength = opts.shadow_strength;
+        rust_options.smooth_normals = opts.smooth_normals;
+        rust_options.smooth_angle = opts.smooth_angle;
+        rust_options.build_plate = opts.build_plate;
+        rust_options.grid_spacing = opts.grid_spacing;
+        rust_options.bed_shape = bed_shape_from_string(&opts.bed_shape)?;
+        rust_options.bed_width = opts.bed_width;
+        rust_options.bed_depth = opts.bed_depth;
+        rust_options.dimensions = opts.dimensions;
+        rust_options.dimension_lines = opts.dimension_lines;
+        rust_options.dimension_units = dimension_units_from_string(&opts.dimension_units)?;
+        rust_options.animation = opts
+            .animation
+            .as_deref()
+            .map(animation_from_string)
+            .transpose()?;
+        rust_options.frame_rate = opts.frame_rate;
+        rust_options.loop_count = opts.loop_count;
+        rust_options.gif_palette = gif_palette_from_string(&opts.gif_palette)?;
+        rust_options.contact_sheet = opts
+            .contact_sheet
+            .iter()
+            .map(|view| view_from_string(view))
+            .collect::<PyResult<Vec<ViewPreset>>>()?;
+        rust_options.sheet_columns = opts.sheet_columns;
+        rust_options.sheet_labels = opts.sheet_labels;
+        rust_options.tile_size = opts.tile_size;
+        rust_options.overview_columns = opts.overview_columns;
+        rust_options.explode = opts.explode;
+        rust_options.section = opts
+            .section
+            .as_deref()
+            .map(section_from_string)
+            .transpose()?;
+        rust_options.section_normal = opts.section_normal.map(|(x, y, z)| [x, y, z]);
+        rust_options.section_offset = opts.section_offset;
+        rust_options.section_color = opts.section_color.clone();
+        rust_options.overhangs = opts.overhangs;
+        rust_options.overhang_angle = opts.overhang_angle;
+        rust_options.overhang_color = opts.overhang_color.clone();
+        rust_options.bed_contact_color = opts.bed_contact_color.clone();
+        rust_options.color_mode = color_mode_from_string(&opts.color_mode)?;
+        rust_options.auto_orient = opts
+            .auto_orient
+            .as_deref()
+            .map(auto_orient_from_string)
+            .transpose()?;
+        rust_options.up_axis = up_axis_from_string(&opts.up_axis)?;
+        rust_options.units = units_from_string(&opts.units)?;
+        rust_options.quality = opts.quality;
+        rust_options.lossless = opts.lossless;
+        rust_options.jpeg_quality = opts.jpeg_quality;
+        rust_options.png_compression = png_compression_from_string(&opts.png_compression)?;
+        rust_options.png_palette = opts.png_palette;
+        rust_options.images_per_file = opts.images_per_file;
+        rust_options.depth_map = opts.depth_map;
+        rust_options.normal_map = opts.normal_map;
     }
 
-    rust_options.images_per_file = 1;
     Ok(rust_options)
 }
 
@@ -122,6 +869,162 @@ fn format_from_string(value: &str) -> PyResult<Format> {
     match normalize_format_string(value)?.as_str() {
         "png" => Ok(Format::Png),
         "jpg" => Ok(Format::Jpg),
+        "webp" => Ok(Format::Webp),
+        "avif" => Ok(Format::Avif),
+        "bmp" => Ok(Format::Bmp),
+        "tiff" => Ok(Format::Tiff),
+        "qoi" => Ok(Format::Qoi),
+        _ => unreachable!(),
+    }
+}
+
+fn thumbnail_selection_from_string(value: &str) -> PyResult<ThumbnailSelection> {
+    match normalize_thumbnail_selection_string(value)?.as_str() {
+        "main" => Ok(ThumbnailSelection::Main),
+        "largest" => Ok(ThumbnailSelection::Largest),
+        "smallest-fitting" => Ok(ThumbnailSelection::SmallestFitting),
+        "closest-aspect" => Ok(ThumbnailSelection::ClosestAspect),
+        _ => unreachable!(),
+    }
+}
+
+fn projection_from_string(value: &str) -> PyResult<Projection> {
+    match normalize_projection_string(value)?.as_str() {
+        "perspective" => Ok(Projection::Perspective),
+        "orthographic" => Ok(Projection::Orthographic),
+        _ => unreachable!(),
+    }
+}
+
+fn view_from_string(value: &str) -> PyResult<ViewPreset> {
+    match normalize_view_string(value)?.as_str() {
+        "front" => Ok(ViewPreset::Front),
+        "back" => Ok(ViewPreset::Back),
+        "left" => Ok(ViewPreset::Left),
+        "right" => Ok(ViewPreset::Right),
+        "top" => Ok(ViewPreset::Top),
+        "bottom" => Ok(ViewPreset::Bottom),
+        "iso" => Ok(ViewPreset::Iso),
+        "dimetric" => Ok(ViewPreset::Dimetric),
+        _ => unreachable!(),
+    }
+}
+
+fn render_mode_from_string(value: &str) -> PyResult<RenderMode> {
+    match normalize_render_mode_string(value)?.as_str() {
+        "shaded" => Ok(RenderMode::Shaded),
+        "wireframe" => Ok(RenderMode::Wireframe),
+        "shaded-wireframe" => Ok(RenderMode::ShadedWireframe),
+        "points" => Ok(RenderMode::Points),
+        _ => unreachable!(),
+    }
+}
+
+fn lighting_from_string(value: &str) -> PyResult<LightingRig> {
+    match normalize_lighting_string(value)?.as_str() {
+        "headlight" => Ok(LightingRig::Headlight),
+        "studio" => Ok(LightingRig::Studio),
+        "top-down" => Ok(LightingRig::TopDown),
+        _ => unreachable!(),
+    }
+}
+
+fn material_from_string(value: &str) -> PyResult<MaterialPreset> {
+    match normalize_material_string(value)?.as_str() {
+        "matte" => Ok(MaterialPreset::Matte),
+        "glossy-plastic" => Ok(MaterialPreset::GlossyPlastic),
+        "metal" => Ok(MaterialPreset::Metal),
+        "resin" => Ok(MaterialPreset::Resin),
+        _ => unreachable!(),
+    }
+}
+
+fn bed_shape_from_string(value: &str) -> PyResult<BedShape> {
+    match normalize_bed_shape_string(value)?.as_str() {
+        "rectangle" => Ok(BedShape::Rectangle),
+        "circle" => Ok(BedShape::Circle),
+        _ => unreachable!(),
+    }
+}
+
+fn dimension_units_from_string(value: &str) -> PyResult<DimensionUnits> {
+    match normalize_dimension_units_string(value)?.as_str() {
+        "mm" => Ok(DimensionUnits::Mm),
+        "cm" => Ok(DimensionUnits::Cm),
+        "inch" => Ok(DimensionUnits::Inch),
+        _ => unreachable!(),
+    }
+}
+
+fn color_mode_from_string(value: &str) -> PyResult<ColorMode> {
+    match normalize_color_mode_string(value)?.as_str() {
+        "single" => Ok(ColorMode::Single),
+        "height" => Ok(ColorMode::Height),
+        "part" => Ok(ColorMode::Part),
+        "file-name" => Ok(ColorMode::FileName),
+        _ => unreachable!(),
+    }
+}
+
+fn auto_orient_from_string(value: &str) -> PyResult<AutoOrient> {
+    match normalize_auto_orient_string(value)?.as_str() {
+        "largest-face" => Ok(AutoOrient::LargestFace),
+        "lowest-center" => Ok(AutoOrient::LowestCenter),
+        _ => unreachable!(),
+    }
+}
+
+fn up_axis_from_string(value: &str) -> PyResult<UpAxis> {
+    match normalize_up_axis_string(value)?.as_str() {
+        "auto" => Ok(UpAxis::Auto),
+        "y" => Ok(UpAxis::Y),
+        "z" => Ok(UpAxis::Z),
+        _ => unreachable!(),
+    }
+}
+
+fn units_from_string(value: &str) -> PyResult<ModelUnits> {
+    match normalize_units_string(value)?.as_str() {
+        "auto" => Ok(ModelUnits::Auto),
+        "mm" => Ok(ModelUnits::Mm),
+        "cm" => Ok(ModelUnits::Cm),
+        "m" => Ok(ModelUnits::M),
+        "inch" => Ok(ModelUnits::Inch),
+        _ => unreachable!(),
+    }
+}
+
+fn png_compression_from_string(value: &str) -> PyResult<PngCompression> {
+    match normalize_png_compression_string(value)?.as_str() {
+        "fast" => Ok(PngCompression::Fast),
+        "default" => Ok(PngCompression::Default),
+        "best" => Ok(PngCompression::Best),
+        _ => unreachable!(),
+    }
+}
+
+fn section_from_string(value: &str) -> PyResult<SectionAxis> {
+    match normalize_section_string(value)?.as_str() {
+        "x" => Ok(SectionAxis::X),
+        "y" => Ok(SectionAxis::Y),
+        "z" => Ok(SectionAxis::Z),
+        _ => unreachable!(),
+    }
+}
+
+fn animation_from_string(value: &str) -> PyResult<AnimationFormat> {
+    match normalize_animation_string(value)?.as_str() {
+        "gif" => Ok(AnimationFormat::Gif),
+        "apng" => Ok(AnimationFormat::Apng),
+        "webp" => Ok(AnimationFormat::Webp),
+        _ => unreachable!(),
+    }
+}
+
+fn gif_palette_from_string(value: &str) -> PyResult<GifPalette> {
+    match normalize_gif_palette_string(value)?.as_str() {
+        "global" => Ok(GifPalette::Global),
+        "per-frame" => Ok(GifPalette::PerFrame),
         _ => unreachable!(),
     }
 }
@@ -163,13 +1066,71 @@ fn generate_thumbnail_bytes_py<'py>(
     Ok(PyBytes::new_bound(py, &bytes))
 }
 
+#[pyfunction]
+#[pyo3(name = "generate_thumbnail_images")]
+#[pyo3(signature = (file, options=None))]
+fn generate_thumbnail_images_py(
+    file: &Bound<'_, PyAny>,
+    options: Option<PyRef<'_, PyThumbnailOptions>>,
+) -> PyResult<Vec<PyThumbnailImage>> {
+    let file_path: PathBuf = file.extract()?;
+    let rust_options = py_options_to_rust(options)?;
+
+    let images =
+        generate_images_for_file(&file_path, &rust_options).map_err(thumbnail_error_to_pyerr)?;
+
+    Ok(images.into_iter().map(PyThumbnailImage::from).collect())
+}
+
+#[pyfunction]
+#[pyo3(name = "generate_overview_for_paths")]
+#[pyo3(signature = (paths, output, options=None))]
+fn generate_overview_for_paths_py(
+    paths: &Bound<'_, PyAny>,
+    output: &Bound<'_, PyAny>,
+    options: Option<PyRef<'_, PyThumbnailOptions>>,
+) -> PyResult<()> {
+    let input_paths: Vec<PathBuf> = paths.extract()?;
+    let output_path: PathBuf = output.extract()?;
+    let rust_options = py_options_to_rust(options)?;
+
+    generate_overview_for_paths(&input_paths, &output_path, &rust_options)
+        .map_err(thumbnail_error_to_pyerr)
+}
+
+#[pyfunction]
+#[pyo3(name = "generate_overview_bytes")]
+#[pyo3(signature = (paths, options=None))]
+fn generate_overview_bytes_py<'py>(
+    py: Python<'py>,
+    paths: &Bound<'py, PyAny>,
+    options: Option<PyRef<'py, PyThumbnailOptions>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let input_paths: Vec<PathBuf> = paths.extract()?;
+    let rust_options = py_options_to_rust(options)?;
+
+    let bytes = generate_overview_bytes_for_paths(&input_paths, &rust_options)
+        .map_err(thumbnail_error_to_pyerr)?;
+
+    Ok(PyBytes::new_bound(py, &bytes))
+}
+
 #[pymodule]
 pub fn _core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyThumbnailOptions>()?;
+    m.add_class::<PyThumbnailImage>()?;
     m.add_function(wrap_pyfunction!(generate_thumbnail_for_file_py, m)?)?;
     m.add_function(wrap_pyfunction!(generate_thumbnail_bytes_py, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_thumbnail_images_py, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_overview_for_paths_py, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_overview_bytes_py, m)?)?;
     m.add("FORMAT_PNG", "png")?;
     m.add("FORMAT_JPG", "jpg")?;
+    m.add("FORMAT_WEBP", "webp")?;
+    m.add("FORMAT_AVIF", "avif")?;
+    m.add("FORMAT_BMP", "bmp")?;
+    m.add("FORMAT_TIFF", "tiff")?;
+    m.add("FORMAT_QOI", "qoi")?;
     m.add(
         "MeshThumbnailError",
         m.py().get_type_bound::<PyThumbnailError>(),
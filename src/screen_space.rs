@@ -0,0 +1,138 @@
+use three_d::*;
+
+///
+/// World space positions and normals reconstructed per pixel from a depth buffer.
+/// Used by the post processing passes that run on the CPU after the model has been rendered.
+/// Only the depth and a packed normal are kept per pixel, since supersampled views can have tens of millions of pixels.
+///
+pub struct GBuffer {
+    pub width: usize,
+    pub height: usize,
+    /// Depth buffer value of every pixel, 1.0 for background pixels.
+    depth: Vec<f32>,
+    /// Surface normal of every pixel packed by `pack_normal`, 0 for background pixels and pixels without neighbours.
+    normals: Vec<u32>,
+    inverse_view_projection: Mat4,
+}
+
+impl GBuffer {
+    /// Takes the depth buffer of a view rendered with `camera` and reconstructs the normals of every covered pixel.
+    pub fn new(depth: Vec<f32>, width: u32, height: u32, camera: &Camera) -> Self {
+        let inverse_view_projection = (camera.projection() * camera.view())
+            .invert()
+            .unwrap_or(Mat4::identity());
+
+        let mut gbuffer = Self {
+            width: width as usize,
+            height: height as usize,
+            depth,
+            normals: Vec::new(),
+            inverse_view_projection,
+        };
+        gbuffer.normals = gbuffer.reconstruct_normals();
+
+        gbuffer
+    }
+
+    /// Whether the pixel at `index` shows the model rather than the background.
+    pub fn is_covered(&self, index: usize) -> bool {
+        self.depth[index] < 1.0
+    }
+
+    /// World position of the pixel at `index`, `None` for background pixels.
+    pub fn position(&self, index: usize) -> Option<Vec3> {
+        if !self.is_covered(index) {
+            return None;
+        }
+
+        let x = (index % self.width) as f32 + 0.5;
+        let y = (index / self.width) as f32 + 0.5;
+        Some(self.unproject(x, y, self.depth[index]))
+    }
+
+    /// Surface normal of the pixel at `index`, `None` for background pixels and pixels without neighbours.
+    pub fn normal(&self, index: usize) -> Option<Vec3> {
+        unpack_normal(self.normals[index])
+    }
+
+    /// World position of the pixel coordinate `(x, y)` at the given depth buffer value.
+    /// Rows are counted from the top, like the pixels read back from the render target.
+    pub fn unproject(&self, x: f32, y: f32, depth: f32) -> Vec3 {
+        let ndc = vec4(
+            2.0 * x / self.width as f32 - 1.0,
+            1.0 - 2.0 * y / self.height as f32,
+            2.0 * depth - 1.0,
+            1.0,
+        );
+        let world = self.inverse_view_projection * ndc;
+
+        world.truncate() / world.w
+    }
+
+    /// Index of the pixel at `(x + dx, y + dy)`, or `None` when it falls outside the image.
+    pub fn offset_index(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<usize> {
+        let nx = x.checked_add_signed(dx)?;
+        let ny = y.checked_add_signed(dy)?;
+
+        if nx >= self.width || ny >= self.height {
+            return None;
+        }
+
+        Some(ny * self.width + nx)
+    }
+
+    // Central differences of the neighbouring positions, one sided at borders
+    fn reconstruct_normals(&self) -> Vec<u32> {
+        (0..self.depth.len())
+            .map(|i| {
+                let Some(center) = self.position(i) else {
+                    return 0;
+                };
+                let x = i % self.width;
+                let y = i / self.width;
+                let at = |dx, dy| {
+                    self.offset_index(x, y, dx, dy)
+                        .and_then(|j| self.position(j))
+                        .unwrap_or(center)
+                };
+
+                let tangent_x = at(1, 0) - at(-1, 0);
+                let tangent_y = at(0, 1) - at(0, -1);
+                let normal = tangent_x.cross(tangent_y);
+
+                if normal.magnitude2() == 0.0 {
+                    return 0;
+                }
+
+                // Orient the normal towards the camera, independent of the row order of the image
+                let towards_camera = self.unproject(x as f32 + 0.5, y as f32 + 0.5, 0.0)
+                    - self.unproject(x as f32 + 0.5, y as f32 + 0.5, 1.0);
+
+                if normal.dot(towards_camera) < 0.0 {
+                    pack_normal(-normal.normalize())
+                } else {
+                    pack_normal(normal.normalize())
+                }
+            })
+            .collect()
+    }
+}
+
+// Marks a packed normal as present, so 0 can stand for a missing one
+const NORMAL_PRESENT: u32 = 1 << 30;
+
+// Stores each component of a unit vector in 10 bits
+fn pack_normal(normal: Vec3) -> u32 {
+    let quantize = |v: f32| ((v.clamp(-1.0, 1.0) * 0.5 + 0.5) * 1023.0).round() as u32;
+
+    NORMAL_PRESENT | (quantize(normal.x) << 20) | (quantize(normal.y) << 10) | quantize(normal.z)
+}
+
+fn unpack_normal(packed: u32) -> Option<Vec3> {
+    if packed & NORMAL_PRESENT == 0 {
+        return None;
+    }
+
+    let component = |shift: u32| ((packed >> shift) & 1023) as f32 / 1023.0 * 2.0 - 1.0;
+    Some(vec3(component(20), component(10), component(0)).normalize())
+}
@@ -1,8 +1,78 @@
+use clap::ValueEnum;
 use three_d::*;
 
+/// Arrangement of the lights used by [SolidMaterial], positioned relative to the camera.
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum LightingRig {
+    /// A single light at the camera position.
+    #[default]
+    Headlight,
+    /// Key, fill and back light around the camera.
+    Studio,
+    /// A light straight above the model with a weak headlight fill.
+    TopDown,
+}
+
+impl ToString for LightingRig {
+    fn to_string(&self) -> String {
+        match self {
+            LightingRig::Headlight => String::from("headlight"),
+            LightingRig::Studio => String::from("studio"),
+            LightingRig::TopDown => String::from("top-down"),
+        }
+    }
+}
+
+/// Surface response presets for [SolidMaterial].
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum MaterialPreset {
+    #[default]
+    Matte,
+    GlossyPlastic,
+    Metal,
+    Resin,
+}
+
+impl ToString for MaterialPreset {
+    fn to_string(&self) -> String {
+        match self {
+            MaterialPreset::Matte => String::from("matte"),
+            MaterialPreset::GlossyPlastic => String::from("glossy-plastic"),
+            MaterialPreset::Metal => String::from("metal"),
+            MaterialPreset::Resin => String::from("resin"),
+        }
+    }
+}
+
+/// Coloring of faces by their angle to the build direction, used for printability previews.
+#[derive(Debug, Clone, Copy)]
+pub struct OverhangHighlight {
+    /// Build direction in world space.
+    pub up: Vec3,
+    /// Height of the build plate along `up`, downward faces at or below it touch the bed.
+    pub bed_height: f32,
+    /// Angle in degrees from vertical beyond which a downward facing surface is an overhang.
+    pub angle: f32,
+    /// Color of overhanging faces.
+    pub overhang_color: Srgba,
+    /// Color of faces resting on the build plate.
+    pub bed_color: Srgba,
+}
+
+/// Coloring of the surface by its height along the build direction, from blue at the bottom to red at the top.
+#[derive(Debug, Clone, Copy)]
+pub struct HeightGradient {
+    /// Build direction in world space.
+    pub up: Vec3,
+    /// Height along `up` of the lowest point, drawn in the first gradient color.
+    pub low: f32,
+    /// Height along `up` of the highest point, drawn in the last gradient color.
+    pub high: f32,
+}
+
 ///
-/// A material that renders a [Geometry] in a color defined by multiplying a color with an optional texture and optional per vertex colors.
-/// This material is not affected by lights.
+/// A material that renders a [Geometry] in a single color, shaded by the lights of a [LightingRig].
+/// The ambient, specular, shininess, rim and metallic terms start from a [MaterialPreset] and can be changed per material.
 ///
 #[derive(Clone, Default)]
 pub struct SolidMaterial {
@@ -12,6 +82,29 @@ pub struct SolidMaterial {
     pub render_states: RenderStates,
     /// Whether this material should be treated as a transparent material (An object needs to be rendered differently depending on whether it is transparent or opaque).
     pub is_transparent: bool,
+    /// Lights used to shade the surface.
+    pub lighting: LightingRig,
+    /// Constant light added regardless of the surface orientation.
+    pub ambient: f32,
+    /// Strength of the specular highlight.
+    pub specular: f32,
+    /// Exponent of the specular highlight, higher values give smaller highlights.
+    pub shininess: f32,
+    /// Strength of the rim light along silhouettes.
+    pub rim: f32,
+    /// How much the specular highlight is tinted by the surface color, 1.0 for metals.
+    pub metallic: f32,
+    /// Shade with the vertex normals of the geometry instead of flat face normals.
+    /// The geometry must provide normals when this is enabled.
+    pub smooth_normals: bool,
+    /// Section plane in world space as normal and distance from the origin, everything in front of it is cut away.
+    pub clip_plane: Option<Vec4>,
+    /// Color of the cap covering the cut, back faces seen through the cut are drawn in it.
+    pub cap_color: Srgba,
+    /// Colors overhangs and bed contact faces instead of the surface color when set.
+    pub overhangs: Option<OverhangHighlight>,
+    /// Replaces the surface color with a gradient along the build direction when set.
+    pub height_gradient: Option<HeightGradient>,
 }
 
 impl SolidMaterial {
@@ -30,6 +123,7 @@ impl SolidMaterial {
             color: cpu_material.albedo,
             is_transparent: false,
             render_states: RenderStates::default(),
+            ..Self::default().with_preset(MaterialPreset::Matte)
         }
     }
 
@@ -43,6 +137,7 @@ impl SolidMaterial {
                 blend: Blend::TRANSPARENCY,
                 ..Default::default()
             },
+            ..Self::default().with_preset(MaterialPreset::Matte)
         }
     }
 
@@ -52,6 +147,84 @@ impl SolidMaterial {
             color: physical_material.albedo,
             render_states: physical_material.render_states,
             is_transparent: physical_material.is_transparent,
+            ..Self::default().with_preset(MaterialPreset::Matte)
+        }
+    }
+
+    /// Replaces the ambient, specular, shininess, rim and metallic terms with the values of a [MaterialPreset].
+    pub fn with_preset(mut self, preset: MaterialPreset) -> Self {
+        (
+            self.ambient,
+            self.specular,
+            self.shininess,
+            self.rim,
+            self.metallic,
+        ) = match preset {
+            MaterialPreset::Matte => (0.0, 0.0, 1.0, 0.2, 0.0),
+            MaterialPreset::GlossyPlastic => (0.05, 0.5, 64.0, 0.15, 0.0),
+            MaterialPreset::Metal => (0.1, 0.9, 32.0, 0.1, 1.0),
+            MaterialPreset::Resin => (0.15, 0.35, 96.0, 0.35, 0.0),
+        };
+        self
+    }
+
+    /// Sets the lights used to shade the surface.
+    pub fn with_lighting(mut self, lighting: LightingRig) -> Self {
+        self.lighting = lighting;
+        self
+    }
+
+    /// Selects shading with the vertex normals of the geometry instead of flat face normals.
+    pub fn with_smooth_normals(mut self, smooth_normals: bool) -> Self {
+        self.smooth_normals = smooth_normals;
+        self
+    }
+
+    /// Cuts the surface with a section plane, or removes the cut when `clip_plane` is `None`.
+    pub fn with_clip_plane(mut self, clip_plane: Option<Vec4>, cap_color: Srgba) -> Self {
+        self.clip_plane = clip_plane;
+        self.cap_color = cap_color;
+        self
+    }
+
+    /// Colors faces by their angle to the build direction, or disables it when `overhangs` is `None`.
+    pub fn with_overhangs(mut self, overhangs: Option<OverhangHighlight>) -> Self {
+        self.overhangs = overhangs;
+        self
+    }
+
+    /// Colors the surface by its height along the build direction, or disables it when `height_gradient` is `None`.
+    pub fn with_height_gradient(mut self, height_gradient: Option<HeightGradient>) -> Self {
+        self.height_gradient = height_gradient;
+        self
+    }
+
+    /// Light directions (pointing towards the light) and intensities for the current [LightingRig].
+    /// A zero direction means the light sits at the camera.
+    fn light_directions(&self, viewer: &dyn Viewer) -> [(Vec3, f32); 3] {
+        // The rows of the view matrix are the camera axes in world space
+        let view = viewer.view();
+        let right = vec3(view.x.x, view.y.x, view.z.x);
+        let up = vec3(view.x.y, view.y.y, view.z.y);
+        let backward = vec3(view.x.z, view.y.z, view.z.z);
+        let camera_light = vec3(0.0, 0.0, 0.0);
+
+        match self.lighting {
+            LightingRig::Headlight => [
+                (camera_light, 1.0),
+                (camera_light, 0.0),
+                (camera_light, 0.0),
+            ],
+            LightingRig::Studio => [
+                ((backward + up * 0.5 - right * 0.7).normalize(), 0.8),
+                ((backward + right * 0.8 - up * 0.1).normalize(), 0.35),
+                ((up * 0.8 - backward).normalize(), 0.4),
+            ],
+            LightingRig::TopDown => [
+                (vec3(0.0, 1.0, 0.0), 0.85),
+                (camera_light, 0.3),
+                (camera_light, 0.0),
+            ],
         }
     }
 }
@@ -64,11 +237,37 @@ impl FromCpuMaterial for SolidMaterial {
 
 impl Material for SolidMaterial {
     fn id(&self) -> EffectMaterialId {
-        EffectMaterialId(0x0000)
+        // Each shader variant needs its own id, programs are cached per id
+        let mut id = 0x0000;
+        if self.smooth_normals {
+            id |= 0x0001;
+        }
+        if self.clip_plane.is_some() {
+            id |= 0x0002;
+        }
+        if self.overhangs.is_some() {
+            id |= 0x0004;
+        }
+        if self.height_gradient.is_some() {
+            id |= 0x0008;
+        }
+        EffectMaterialId(id)
     }
 
     fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
         let mut shader = String::new();
+        if self.smooth_normals {
+            shader.push_str("#define USE_VERTEX_NORMALS\n");
+        }
+        if self.clip_plane.is_some() {
+            shader.push_str("#define USE_CLIP_PLANE\n");
+        }
+        if self.overhangs.is_some() {
+            shader.push_str("#define USE_OVERHANGS\n");
+        }
+        if self.height_gradient.is_some() {
+            shader.push_str("#define USE_HEIGHT_GRADIENT\n");
+        }
         shader.push_str(ColorMapping::fragment_shader_source());
         shader.push_str(include_str!("solid_material_shader.frag"));
         shader
@@ -77,6 +276,44 @@ impl Material for SolidMaterial {
     fn use_uniforms(&self, program: &Program, viewer: &dyn Viewer, _lights: &[&dyn Light]) {
         program.use_uniform("surfaceColor", self.color.to_linear_srgb());
         program.use_uniform_if_required("cameraPosition", viewer.position());
+
+        let lights = self.light_directions(viewer);
+        program.use_uniform_if_required("lightDirection0", lights[0].0);
+        program.use_uniform_if_required("lightDirection1", lights[1].0);
+        program.use_uniform_if_required("lightDirection2", lights[2].0);
+        program.use_uniform_if_required(
+            "lightIntensities",
+            vec3(lights[0].1, lights[1].1, lights[2].1),
+        );
+        program.use_uniform_if_required("ambient", self.ambient);
+        program.use_uniform_if_required("specular", self.specular);
+        program.use_uniform_if_required("shininess", self.shininess);
+        program.use_uniform_if_required("rim", self.rim);
+        program.use_uniform_if_required("metallic", self.metallic);
+
+        if let Some(clip_plane) = self.clip_plane {
+            program.use_uniform("clipPlane", clip_plane);
+            program.use_uniform_if_required("capColor", self.cap_color.to_linear_srgb());
+        }
+
+        if let Some(overhangs) = self.overhangs {
+            program.use_uniform_if_required("upDirection", overhangs.up.normalize());
+            program.use_uniform_if_required("bedHeight", overhangs.bed_height);
+            program.use_uniform_if_required(
+                "overhangSine",
+                overhangs.angle.clamp(0.0, 90.0).to_radians().sin(),
+            );
+            program.use_uniform_if_required(
+                "overhangColor",
+                overhangs.overhang_color.to_linear_srgb(),
+            );
+            program.use_uniform_if_required("bedColor", overhangs.bed_color.to_linear_srgb());
+        }
+
+        if let Some(gradient) = self.height_gradient {
+            program.use_uniform_if_required("gradientUp", gradient.up.normalize());
+            program.use_uniform_if_required("gradientRange", vec2(gradient.low, gradient.high));
+        }
     }
 
     fn render_states(&self) -> RenderStates {
@@ -1,30 +1,120 @@
 uniform vec4 surfaceColor;
 uniform vec3 cameraPosition;
+uniform vec3 lightDirection0;
+uniform vec3 lightDirection1;
+uniform vec3 lightDirection2;
+uniform vec3 lightIntensities;
+uniform float ambient;
+uniform float specular;
+uniform float shininess;
+uniform float rim;
+uniform float metallic;
+#ifdef USE_CLIP_PLANE
+uniform vec4 clipPlane;
+uniform vec4 capColor;
+#endif
+#ifdef USE_HEIGHT_GRADIENT
+uniform vec3 gradientUp;
+uniform vec2 gradientRange;
+#endif
+#ifdef USE_OVERHANGS
+uniform vec3 upDirection;
+uniform float bedHeight;
+uniform float overhangSine;
+uniform vec4 overhangColor;
+uniform vec4 bedColor;
+#endif
 
 in vec3 pos;
+#ifdef USE_VERTEX_NORMALS
+in vec3 nor;
+#endif
 
 layout (location = 0) out vec4 outColor;
 
+vec3 shadeLight(vec3 direction, float intensity, vec3 normal, vec3 viewDir, vec3 baseColor)
+{
+    // A zero direction marks a light that moves with the camera
+    vec3 lightDir = length(direction) > 0.0 ? normalize(direction) : viewDir;
+
+    float diffuse = max(dot(normal, lightDir), 0.0);
+    vec3 halfDir = normalize(lightDir + viewDir);
+    float highlight = diffuse > 0.0 ? pow(max(dot(normal, halfDir), 0.0), shininess) : 0.0;
+    vec3 specularColor = mix(vec3(1.0), baseColor, metallic);
+
+    return intensity * (baseColor * diffuse * (1.0 - 0.5 * metallic) + specularColor * specular * highlight);
+}
+
+#ifdef USE_HEIGHT_GRADIENT
+vec3 heightColor(float t)
+{
+    // Blue, green, yellow and red stops, evenly spaced over the height of the model
+    vec3 stops[4] = vec3[4](
+        vec3(0.05, 0.20, 0.65),
+        vec3(0.10, 0.55, 0.25),
+        vec3(0.90, 0.75, 0.10),
+        vec3(0.80, 0.12, 0.08)
+    );
+    float scaled = clamp(t, 0.0, 1.0) * 3.0;
+    int index = min(int(scaled), 2);
+    return mix(stops[index], stops[index + 1], scaled - float(index));
+}
+#endif
+
 void main()
 {
+#ifdef USE_CLIP_PLANE
+    if (dot(clipPlane.xyz, pos) > clipPlane.w) {
+        discard;
+    }
+#endif
+
     // Compute face normal using fragment position derivatives
     vec3 dx = dFdx(pos);
     vec3 dy = dFdy(pos);
     vec3 normal = normalize(cross(dx, dy));
+    vec3 faceNormal = normal;
+
+#ifdef USE_VERTEX_NORMALS
+    // Interpolated vertex normal, flipped to the side of the face normal so the winding order does not matter
+    vec3 vertexNormal = normalize(nor);
+    normal = dot(vertexNormal, normal) < 0.0 ? -vertexNormal : vertexNormal;
+#endif
 
-    // Make light move with the camera for solid shading effect
     vec3 viewDir = normalize(cameraPosition - pos);
-    vec3 lightDir = normalize(viewDir); 
+    vec3 baseColor = surfaceColor.xyz;
+
+#ifdef USE_HEIGHT_GRADIENT
+    float height = dot(pos, gradientUp);
+    baseColor = heightColor((height - gradientRange.x) / max(gradientRange.y - gradientRange.x, 1e-6));
+#endif
+
+#ifdef USE_OVERHANGS
+    // The face normal points towards the viewer, which is outwards for the visible side of a closed mesh
+    float downward = dot(faceNormal, -upDirection);
+    if (downward > 0.99 && dot(pos, upDirection) <= bedHeight) {
+        baseColor = bedColor.xyz;
+    } else if (downward > overhangSine) {
+        baseColor = overhangColor.xyz;
+    }
+#endif
+
+#ifdef USE_CLIP_PLANE
+    // Inner faces are only visible through the cut, shade them as a flat cap lying in the section plane
+    if (!gl_FrontFacing) {
+        normal = dot(clipPlane.xyz, viewDir) < 0.0 ? -clipPlane.xyz : clipPlane.xyz;
+        baseColor = capColor.xyz;
+    }
+#endif
 
     // Compute lighting
-    float diffuse = max(dot(normal, lightDir), 0.0);
+    vec3 shadedColor = baseColor * ambient;
+    shadedColor += shadeLight(lightDirection0, lightIntensities.x, normal, viewDir, baseColor);
+    shadedColor += shadeLight(lightDirection1, lightIntensities.y, normal, viewDir, baseColor);
+    shadedColor += shadeLight(lightDirection2, lightIntensities.z, normal, viewDir, baseColor);
 
     // Soft rim light effect
-    float rim = pow(1.0 - max(dot(viewDir, normal), 0.0), 3.0);
+    shadedColor += pow(1.0 - max(dot(viewDir, normal), 0.0), 3.0) * rim;
 
-    // Merge colors
-    vec3 baseColor = surfaceColor.xyz;
-    vec3 shadedColor = baseColor * diffuse + rim * 0.2;
-    
     outColor = vec4(shadedColor, 1.0);
-}
\ No newline at end of file
+}
@@ -0,0 +1,104 @@
+use image::{ImageBuffer, Luma, Rgba, RgbaImage, imageops, imageops::FilterType::Nearest};
+use three_d::*;
+
+use crate::fit_to_canvas;
+use crate::screen_space::GBuffer;
+
+pub type DepthImage = ImageBuffer<Luma<u16>, Vec<u16>>;
+
+///
+/// Depth and normals of a rendered view, aligned pixel for pixel with its color image.
+/// Every resize samples the nearest pixel, so depths and normals along silhouettes are never blended with the background.
+///
+pub struct SurfaceMaps {
+    /// Distance from the camera, 65535 at the nearest and 1 at the farthest corner of the model's bounding box, 0 for the background.
+    pub depth: DepthImage,
+    /// Normals in view space, X right, Y up and Z towards the camera, mapped from -1..1 to 0..255. The background is transparent.
+    pub normal: RgbaImage,
+}
+
+impl SurfaceMaps {
+    /// Builds the maps from the reconstructed positions and normals of a view rendered with `camera`.
+    pub fn new(gbuffer: &GBuffer, camera: &Camera, (scene_min, scene_max): (Vec3, Vec3)) -> Self {
+        let view = *camera.view();
+        let view_depth = |position: Vec3| -(view * position.extend(1.0)).z;
+
+        // The bounding box keeps the depth scale of a model the same no matter which parts are hidden
+        let corner_depths: Vec<f32> = (0..8)
+            .map(|corner| {
+                let pick = |bit: u32, min: f32, max: f32| if corner & bit == 0 { min } else { max };
+                view_depth(vec3(
+                    pick(1, scene_min.x, scene_max.x),
+                    pick(2, scene_min.y, scene_max.y),
+                    pick(4, scene_min.z, scene_max.z),
+                ))
+            })
+            .collect();
+        let near = corner_depths.iter().cloned().fold(f32::INFINITY, f32::min);
+        let far = corner_depths
+            .iter()
+            .cloned()
+            .fold(f32::NEG_INFINITY, f32::max);
+        let range = (far - near).max(f32::EPSILON);
+
+        let pixels = 0..gbuffer.width * gbuffer.height;
+
+        let depth = pixels
+            .clone()
+            .map(|i| match gbuffer.position(i) {
+                Some(position) => {
+                    let t = ((view_depth(position) - near) / range).clamp(0.0, 1.0);
+                    1 + ((1.0 - t) * 65534.0).round() as u16
+                }
+                None => 0,
+            })
+            .collect();
+
+        let normal = pixels
+            .flat_map(|i| match gbuffer.normal(i) {
+                Some(normal) => {
+                    let normal = (view * normal.extend(0.0)).truncate().normalize();
+                    let encode = |v: f32| ((v * 0.5 + 0.5).clamp(0.0, 1.0) * 255.0).round() as u8;
+                    [encode(normal.x), encode(normal.y), encode(normal.z), 255]
+                }
+                None => [0, 0, 0, 0],
+            })
+            .collect();
+
+        let (width, height) = (gbuffer.width as u32, gbuffer.height as u32);
+
+        Self {
+            depth: DepthImage::from_raw(width, height, depth).unwrap(),
+            normal: RgbaImage::from_raw(width, height, normal).unwrap(),
+        }
+    }
+
+    /// Scales both maps to `width` x `height`, like downsampling the supersampled color image.
+    pub fn resize(self, width: u32, height: u32) -> Self {
+        if self.depth.width() == width && self.depth.height() == height {
+            return self;
+        }
+
+        Self {
+            depth: imageops::resize(&self.depth, width, height, Nearest),
+            normal: imageops::resize(&self.normal, width, height, Nearest),
+        }
+    }
+
+    /// Applies the crop of an auto cropped color image, `bounds` being the `(x, y, width, height)` of its content.
+    pub fn crop_to_canvas(
+        self,
+        (x, y, crop_width, crop_height): (u32, u32, u32, u32),
+        width: u32,
+        height: u32,
+        margin: f32,
+    ) -> Self {
+        let depth = imageops::crop_imm(&self.depth, x, y, crop_width, crop_height).to_image();
+        let normal = imageops::crop_imm(&self.normal, x, y, crop_width, crop_height).to_image();
+
+        Self {
+            depth: fit_to_canvas(&depth, width, height, margin, Luma([0]), Nearest),
+            normal: fit_to_canvas(&normal, width, height, margin, Rgba([0, 0, 0, 0]), Nearest),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::OnceLock;
+
+use fontdue::{Font, FontSettings};
+
+// DejaVu Sans, bundled so text can be drawn without any fonts installed on the system
+const FONT_DATA: &[u8] = include_bytes!("fonts/DejaVuSans.ttf");
+
+static FONT: OnceLock<Font> = OnceLock::new();
+
+fn font() -> &'static Font {
+    FONT.get_or_init(|| {
+        Font::from_bytes(FONT_DATA, FontSettings::default()).expect("Bundled font is valid")
+    })
+}
+
+/// Width in pixels of `text` drawn at `size` pixels.
+pub fn text_width(text: &str, size: f32) -> f32 {
+    text.chars()
+        .map(|character| font().metrics(character, size).advance_width)
+        .sum()
+}
+
+///
+/// Draws `text` into an image of `width` pixels per row, with the top left corner of the line at `(x, y)`.
+/// When a `halo` color is given the glyphs get a one pixel border in it, so the text stays readable on any background.
+///
+pub fn draw_text(
+    pixels: &mut [[u8; 4]],
+    width: usize,
+    text: &str,
+    (x, y): (f32, f32),
+    size: f32,
+    color: [u8; 4],
+    halo: Option<[u8; 4]>,
+) {
+    let ascent = font()
+        .horizontal_line_metrics(size)
+        .map(|metrics| metrics.ascent)
+        .unwrap_or(size);
+    let baseline = (y + ascent).round() as i64;
+    let mut pen = x;
+
+    let glyphs: Vec<(i64, i64, fontdue::Metrics, Vec<u8>)> = text
+        .chars()
+        .map(|character| {
+            let (metrics, bitmap) = font().rasterize(character, size);
+            let left = pen.round() as i64 + metrics.xmin as i64;
+            let top = baseline - metrics.height as i64 - metrics.ymin as i64;
+            pen += metrics.advance_width;
+            (left, top, metrics, bitmap)
+        })
+        .collect();
+
+    if let Some(halo) = halo {
+        for (dx, dy) in [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (-1, 0),
+            (1, 0),
+            (-1, 1),
+            (0, 1),
+            (1, 1),
+        ] {
+            for (left, top, metrics, bitmap) in glyphs.iter() {
+                blit_glyph(pixels, width, (left + dx, top + dy), metrics, bitmap, halo);
+            }
+        }
+    }
+
+    for (left, top, metrics, bitmap) in glyphs.iter() {
+        blit_glyph(pixels, width, (*left, *top), metrics, bitmap, color);
+    }
+}
+
+fn blit_glyph(
+    pixels: &mut [[u8; 4]],
+    width: usize,
+    (left, top): (i64, i64),
+    metrics: &fontdue::Metrics,
+    bitmap: &[u8],
+    color: [u8; 4],
+) {
+    let height = (pixels.len() / width.max(1)) as i64;
+
+    for row in 0..metrics.height {
+        for column in 0..metrics.width {
+            let px = left + column as i64;
+            let py = top + row as i64;
+
+            if px < 0 || py < 0 || px >= width as i64 || py >= height {
+                continue;
+            }
+
+            let coverage = bitmap[row * metrics.width + column] as f32 / 255.0;
+            blend_pixel(
+                &mut pixels[py as usize * width + px as usize],
+                color,
+                coverage,
+            );
+        }
+    }
+}
+
+/// Blends `color` over `pixel` with the given coverage, keeping the alpha channel at least as opaque as before.
+pub fn blend_pixel(pixel: &mut [u8; 4], color: [u8; 4], coverage: f32) {
+    let alpha = coverage * color[3] as f32 / 255.0;
+
+    if alpha <= 0.0 {
+        return;
+    }
+
+    for channel in 0..3 {
+        pixel[channel] =
+            (pixel[channel] as f32 * (1.0 - alpha) + color[channel] as f32 * alpha).round() as u8;
+    }
+    pixel[3] = (pixel[3] as f32 + (255.0 - pixel[3] as f32) * alpha).round() as u8;
+}
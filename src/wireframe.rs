@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+
+use three_d::*;
+
+///
+/// Builds one cylinder instance per unique triangle edge of `mesh`, for a model drawn with `transform`.
+/// The cylinders are placed around the transformed edges and mapped back, so scaling in `transform` does not stretch them.
+///
+pub fn edge_instances(mesh: &CpuMesh, transform: Mat4, radius: f32) -> Instances {
+    let (transform, inverse) = invertible(transform);
+    let positions = transformed_positions(mesh, transform);
+    let indices = mesh
+        .indices
+        .to_u32()
+        .unwrap_or_else(|| (0..positions.len() as u32).collect());
+
+    let mut edges: HashSet<(u32, u32)> = HashSet::new();
+
+    for triangle in indices.chunks_exact(3) {
+        for (a, b) in [
+            (triangle[0], triangle[1]),
+            (triangle[1], triangle[2]),
+            (triangle[2], triangle[0]),
+        ] {
+            edges.insert((a.min(b), a.max(b)));
+        }
+    }
+
+    Instances {
+        transformations: edges
+            .into_iter()
+            .map(|(a, b)| (positions[a as usize], positions[b as usize]))
+            .filter(|(p1, p2)| p1 != p2)
+            .map(|(p1, p2)| inverse * edge_transform(p1, p2, radius))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Builds one sphere instance per vertex of `mesh`, for a model drawn with `transform`, which does not stretch them either.
+pub fn vertex_instances(mesh: &CpuMesh, transform: Mat4, radius: f32) -> Instances {
+    let (transform, inverse) = invertible(transform);
+
+    Instances {
+        transformations: transformed_positions(mesh, transform)
+            .into_iter()
+            .map(|position| inverse * Mat4::from_translation(position) * Mat4::from_scale(radius))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+// `transform` and its inverse, the identity for both when it cannot be inverted
+fn invertible(transform: Mat4) -> (Mat4, Mat4) {
+    match transform.invert() {
+        Some(inverse) => (transform, inverse),
+        None => (Mat4::identity(), Mat4::identity()),
+    }
+}
+
+fn transformed_positions(mesh: &CpuMesh, transform: Mat4) -> Vec<Vec3> {
+    mesh.positions
+        .to_f32()
+        .into_iter()
+        .map(|position| (transform * position.extend(1.0)).truncate())
+        .collect()
+}
+
+/// Same approach as parse_mesh::edge_transform, with a configurable thickness.
+pub fn edge_transform(p1: Vec3, p2: Vec3, radius: f32) -> Mat4 {
+    Mat4::from_translation(p1)
+        * Into::<Mat4>::into(Quat::from_arc(
+            vec3(1.0, 0.0, 0.0),
+            (p2 - p1).normalize(),
+            None,
+        ))
+        * Mat4::from_nonuniform_scale((p1 - p2).magnitude(), radius, radius)
+}